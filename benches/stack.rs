@@ -0,0 +1,183 @@
+//! Benchmarks of [`Stack`] with many windows compared with the recursive
+//! linked list which was used before it
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/stack.rs"]
+mod stack;
+
+use stack::Stack;
+
+/// Count of windows in the stack
+const DEPTH: i64 = 10_000;
+
+/// Recursive linked list which was used as stack of windows before [`Stack`]
+mod list {
+    /// `Node` is internal type for storing data (of [`i64`]) in [`Stack`]
+    #[derive(Debug, Clone)]
+    struct Node {
+        value: i64,
+        next: Option<Box<Node>>,
+    }
+
+    /// `Stack` is type for storing data (of [`i64`]) in stack
+    #[derive(Debug, Clone)]
+    pub struct Stack {
+        head: Option<Box<Node>>,
+    }
+
+    impl Node {
+        fn new(value: i64) -> Self {
+            Self { value, next: None }
+        }
+
+        fn remove(self, value: i64) -> Option<Box<Self>> {
+            if self.value == value {
+                self.next
+            } else {
+                Some(Box::new(Self {
+                    value: self.value,
+                    next: self.next.and_then(|next| next.remove(value)),
+                }))
+            }
+        }
+
+        fn move_up(self, value: i64) -> Box<Self> {
+            Box::new(Self {
+                value,
+                next: self.remove(value),
+            })
+        }
+
+        fn add(mut self, value: i64) -> Box<Self> {
+            if self.value != value {
+                self.next = self
+                    .next
+                    .map(|next| next.add(value))
+                    .or_else(|| Some(Box::new(Node::new(value))));
+            }
+            Box::new(self)
+        }
+
+        fn get(&self, depth: usize) -> Option<i64> {
+            if depth == 0 {
+                Some(self.value)
+            } else {
+                self.next.as_ref().and_then(|next| next.get(depth - 1))
+            }
+        }
+    }
+
+    impl Stack {
+        pub fn new() -> Self {
+            Self { head: None }
+        }
+
+        pub fn move_up(&mut self, id: i64) -> i64 {
+            self.head = self
+                .head
+                .take()
+                .map(|head| head.move_up(id))
+                .or_else(|| Some(Box::new(Node::new(id))));
+            id
+        }
+
+        pub fn add(&mut self, id: i64) -> i64 {
+            self.head = self
+                .head
+                .take()
+                .map(|head| head.add(id))
+                .or_else(|| Some(Box::new(Node::new(id))));
+            id
+        }
+
+        pub fn remove(&mut self, id: i64) -> i64 {
+            self.head = self.head.take().and_then(|head| head.remove(id));
+            id
+        }
+
+        pub fn get(&self, depth: usize) -> Option<i64> {
+            self.head.as_ref().and_then(|head| head.get(depth))
+        }
+    }
+}
+
+fn filled() -> Stack {
+    let mut s = Stack::new();
+    (0..DEPTH).for_each(|id| {
+        s.add(id);
+    });
+    s
+}
+
+fn filled_list() -> list::Stack {
+    let mut s = list::Stack::new();
+    (0..DEPTH).for_each(|id| {
+        s.add(id);
+    });
+    s
+}
+
+fn move_up(c: &mut Criterion) {
+    let mut group = c.benchmark_group("move_up tail");
+    let s = filled();
+    group.bench_function(BenchmarkId::new("Stack", DEPTH), |b| {
+        b.iter_batched_ref(
+            || s.clone(),
+            |s| s.move_up(black_box(DEPTH - 1)),
+            BatchSize::LargeInput,
+        )
+    });
+    let s = filled_list();
+    group.bench_function(BenchmarkId::new("list", DEPTH), |b| {
+        b.iter_batched_ref(
+            || s.clone(),
+            |s| s.move_up(black_box(DEPTH - 1)),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn remove_add(c: &mut Criterion) {
+    let mut group = c.benchmark_group("remove mid and add to tail");
+    let s = filled();
+    group.bench_function(BenchmarkId::new("Stack", DEPTH), |b| {
+        b.iter_batched_ref(
+            || s.clone(),
+            |s| {
+                s.remove(black_box(DEPTH / 2));
+                s.add(black_box(DEPTH / 2));
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    let s = filled_list();
+    group.bench_function(BenchmarkId::new("list", DEPTH), |b| {
+        b.iter_batched_ref(
+            || s.clone(),
+            |s| {
+                s.remove(black_box(DEPTH / 2));
+                s.add(black_box(DEPTH / 2));
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn get(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get near head");
+    let s = filled();
+    group.bench_function(BenchmarkId::new("Stack", DEPTH), |b| {
+        b.iter(|| s.get(black_box(1)))
+    });
+    let s = filled_list();
+    group.bench_function(BenchmarkId::new("list", DEPTH), |b| {
+        b.iter(|| s.get(black_box(1)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, move_up, remove_add, get);
+criterion_main!(benches);
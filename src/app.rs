@@ -1,4 +1,7 @@
-use clap::{App, AppSettings, Arg};
+use {
+    crate::{control::COMMANDS, highlight::Highlight, input::Source, scope::Scope},
+    clap::{App, AppSettings, Arg, SubCommand},
+};
 
 const PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
 const PKG_NAME: &str = env!("CARGO_PKG_NAME");
@@ -8,10 +11,26 @@ const PKG_DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
 /// Create [`App`] instance for argument parsing
 ///
 /// Arguments:
+///  * `config` - path of configuration file
+///  * `input` - source of actions
 ///  * `device` - device for input events
-///  * `key-alt` - key with Alt behavior
-///  * `key-tab` - key with Tab behavior
-///  * `key-shift` - key with Shift behavior
+///  * `ignore-device` - pattern of names of ignored devices
+///  * `allow-virtual` - accept virtual devices
+///  * `list-devices` - print devices and why they are accepted or rejected
+///  * `key-alt` - keys with Alt behavior
+///  * `key-tab` - keys with Tab behavior
+///  * `key-shift` - keys with Shift behavior
+///  * `key-same-app` - keys with Tab behavior for windows of focused application
+///  * `key-cancel` - keys for cancel switching
+///  * `key-set` - additional key set with own keys and scope
+///  * `scope` - windows for switching between
+///  * `exclude` - criteria of windows which are skipped while switching
+///  * `pin` - criteria of windows which are pinned at fixed slots
+///  * `renderer` - command of helper process which shows windows
+///  * `no-live-preview` - focus selected window only on commit
+///  * `highlight` - highlight selected window instead of focusing it
+///
+/// Subcommands are commands for running daemon over control socket
 pub fn build_app() -> App<'static, 'static> {
     let clap_color_setting = if std::env::var_os("NO_COLOR").is_none() {
         AppSettings::ColoredHelp
@@ -24,6 +43,24 @@ pub fn build_app() -> App<'static, 'static> {
         .author(PKG_AUTHORS)
         .about(PKG_DESCRIPTION)
         .setting(clap_color_setting)
+        .arg(
+            Arg::with_name("config")
+                .short("c")
+                .long("config")
+                .value_name("CONFIG")
+                .help("configuration file (by default $XDG_CONFIG_HOME/sway-alttab/config.toml)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("input")
+                .short("i")
+                .long("input")
+                .value_name("INPUT")
+                .help("source of actions")
+                .takes_value(true)
+                .possible_values(Source::NAMES)
+                .default_value("keyboard"),
+        )
         .arg(
             Arg::with_name("device")
                 .short("d")
@@ -33,11 +70,30 @@ pub fn build_app() -> App<'static, 'static> {
                 .takes_value(true)
                 .multiple(true),
         )
+        .arg(
+            Arg::with_name("ignore-device")
+                .long("ignore-device")
+                .value_name("PATTERN")
+                .help("ignore keyboards with names containing pattern")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("allow-virtual")
+                .long("allow-virtual")
+                .help("accept virtual keyboards (e.g. created via uinput)"),
+        )
+        .arg(
+            Arg::with_name("list-devices")
+                .long("list-devices")
+                .help("print devices and why they are accepted or rejected as keyboards"),
+        )
         .arg(
             Arg::with_name("alt")
                 .long("key-alt")
                 .value_name("KEY_ALT")
-                .help("key for alt-tab with alt behavior")
+                .help("keys for alt-tab with alt behavior (comma separated)")
                 .takes_value(true)
                 .default_value("KEY_LEFTALT"),
         )
@@ -45,7 +101,7 @@ pub fn build_app() -> App<'static, 'static> {
             Arg::with_name("shift")
                 .long("key-shift")
                 .value_name("KEY_SHIFT")
-                .help("key for inverse direction of alt-tab")
+                .help("keys for inverse direction of alt-tab (comma separated)")
                 .takes_value(true)
                 .default_value("KEY_LEFTSHIFT"),
         )
@@ -53,8 +109,98 @@ pub fn build_app() -> App<'static, 'static> {
             Arg::with_name("tab")
                 .long("key-tab")
                 .value_name("KEY_TAB")
-                .help("key for alt-tab with tab behavior")
+                .help("keys for alt-tab with tab behavior (comma separated)")
                 .takes_value(true)
                 .default_value("KEY_TAB"),
         )
+        .arg(
+            Arg::with_name("same-app")
+                .long("key-same-app")
+                .value_name("KEY_SAME_APP")
+                .help("keys for alt-tab between windows of focused application (comma separated)")
+                .takes_value(true)
+                .default_value("KEY_GRAVE"),
+        )
+        .arg(
+            Arg::with_name("cancel")
+                .long("key-cancel")
+                .value_name("KEY_CANCEL")
+                .help("keys for cancel alt-tab and focus initial window (comma separated)")
+                .takes_value(true)
+                .default_value("KEY_ESC"),
+        )
+        .arg(
+            Arg::with_name("key-set")
+                .long("key-set")
+                .value_name("KEY_SET")
+                .help("additional key set, e.g. \"alt=KEY_LEFTMETA scope=workspace\"")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("scope")
+                .long("scope")
+                .value_name("SCOPE")
+                .help("windows for switching between")
+                .takes_value(true)
+                .possible_values(Scope::NAMES)
+                .default_value("all"),
+        )
+        .arg(
+            Arg::with_name("exclude")
+                .long("exclude")
+                .value_name("CRITERIA")
+                .help("skip windows matching criteria, e.g. '[app_id=\"^mako$\"]'")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("pin")
+                .long("pin")
+                .value_name("CRITERIA")
+                .help("pin windows matching criteria at fixed slots in order of switching")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("renderer")
+                .long("renderer")
+                .value_name("COMMAND")
+                .help("run shell command which receives windows as JSON lines while switching")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("no-live-preview")
+                .long("no-live-preview")
+                .help("focus selected window only after finish of switching"),
+        )
+        .arg(
+            Arg::with_name("highlight")
+                .long("highlight")
+                .value_name("HIGHLIGHT")
+                .help("highlight selected window instead of focusing it until finish of switching")
+                .takes_value(true)
+                .possible_values(Highlight::NAMES),
+        )
+        .subcommands(
+            COMMANDS
+                .iter()
+                .map(|(name, about)| SubCommand::with_name(name).about(*about))
+                .map(|command| match command.get_name() {
+                    "pin" => command.arg(
+                        Arg::with_name("slot")
+                            .value_name("SLOT")
+                            .help("number of switches to reach the window"),
+                    ),
+                    "list" => command.arg(
+                        Arg::with_name("json")
+                            .long("json")
+                            .help("print windows with their properties as JSON"),
+                    ),
+                    _ => command,
+                }),
+        )
 }
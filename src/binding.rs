@@ -0,0 +1,105 @@
+use {
+    crate::{
+        input::{Action, Actions, Input},
+        target::Target,
+    },
+    futures_util::{future, StreamExt as _},
+    swayipc_async::{Connection, Event, EventType, Fallible},
+};
+
+/// Create stream of [`Action`] from sway bindings
+///
+/// Sway sends `nop sway-alttab <action>` commands of user bindings to
+/// subscribers of binding events
+pub async fn actions() -> Fallible<Actions> {
+    let events = Connection::new()
+        .await?
+        .subscribe(&[EventType::Binding])
+        .await?;
+
+    Ok(Box::pin(events.filter_map(|event| {
+        future::ready(match event {
+            Ok(Event::Binding(binding)) => input(&binding.binding.command).map(Ok),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        })
+    })))
+}
+
+/// Parse [`Input`] from `nop sway-alttab <action> [target]` command
+fn input(command: &str) -> Option<Input> {
+    let mut words = command.split_whitespace();
+    let (action, target) = match (words.next(), words.next(), words.next(), words.next()) {
+        (Some("nop"), Some(env!("CARGO_PKG_NAME")), Some(action), target) => (action, target),
+        _ => return None,
+    };
+    if words.next().is_some() {
+        return None;
+    }
+
+    let action: Action = action.parse().ok()?;
+    let target = match target {
+        Some(target) => target.parse().ok()?,
+        None => Target::Window,
+    };
+    Some(Input {
+        target,
+        ..Input::from(action)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::input,
+        crate::{
+            input::{Action, Input},
+            target::Target,
+        },
+    };
+
+    fn action(command: &str) -> Option<Action> {
+        input(command).map(|input| input.action)
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(action("nop sway-alttab next"), Some(Action::Next));
+        assert_eq!(action("nop  sway-alttab  prev-app"), Some(Action::PrevApp));
+        assert_eq!(action("nop sway-alttab commit"), Some(Action::Commit));
+    }
+
+    #[test]
+    fn parse_target() {
+        assert_eq!(
+            input("nop sway-alttab next"),
+            Some(Input::from(Action::Next))
+        );
+        assert_eq!(
+            input("nop sway-alttab prev workspace"),
+            Some(Input {
+                target: Target::Workspace,
+                ..Input::from(Action::Prev)
+            })
+        );
+        assert_eq!(
+            input("nop sway-alttab commit output"),
+            Some(Input {
+                target: Target::Output,
+                ..Input::from(Action::Commit)
+            })
+        );
+        assert_eq!(input("nop sway-alttab next screen"), None);
+        assert_eq!(input("nop sway-alttab next window workspace"), None);
+    }
+
+    #[test]
+    fn parse_foreign() {
+        assert_eq!(action("nop"), None);
+        assert_eq!(action("nop sway-alttab"), None);
+        assert_eq!(action("nop sway-alttab jump"), None);
+        assert_eq!(action("nop sway-alttab next prev"), None);
+        assert_eq!(action("nop other next"), None);
+        assert_eq!(action("exec sway-alttab next"), None);
+    }
+}
@@ -0,0 +1,288 @@
+use {
+    crate::{
+        criteria::Criteria,
+        highlight::Highlight,
+        input::Source,
+        keyboard::{self, Detection, Keys},
+        scope::Scope,
+    },
+    clap::ArgMatches,
+    serde::Deserialize,
+    std::{
+        env, iter,
+        path::{Path, PathBuf},
+    },
+    tokio::{fs, io},
+};
+
+/// `Config` is content of configuration file
+///
+/// Names of fields are same as names of long arguments
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub input: Option<String>,
+    pub device: Option<Vec<String>>,
+    pub ignore_device: Option<Vec<String>>,
+    pub allow_virtual: Option<bool>,
+    pub key_alt: Option<String>,
+    pub key_tab: Option<String>,
+    pub key_shift: Option<String>,
+    pub key_same_app: Option<String>,
+    pub key_cancel: Option<String>,
+    pub key_set: Option<Vec<String>>,
+    pub scope: Option<String>,
+    pub exclude: Option<Vec<String>>,
+    pub pin: Option<Vec<String>>,
+    pub renderer: Option<String>,
+    pub no_live_preview: Option<bool>,
+    pub highlight: Option<String>,
+}
+
+impl Config {
+    /// Get default path of configuration file
+    /// `$XDG_CONFIG_HOME/sway-alttab/config.toml`
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(dir.join(env!("CARGO_PKG_NAME")).join("config.toml"))
+    }
+
+    /// Parse configuration from TOML
+    pub fn parse(s: &str) -> Result<Self, String> {
+        toml::from_str(s).map_err(|err| err.to_string())
+    }
+
+    /// Read configuration file at `path`
+    ///
+    /// Missing file is empty configuration if it isn't `required`
+    pub async fn read(path: &Path, required: bool) -> io::Result<Self> {
+        let content = match fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound && !required => {
+                return Ok(Self::default())
+            }
+            Err(err) => return Err(err),
+        };
+
+        Self::parse(&content).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), err),
+            )
+        })
+    }
+
+    /// Get value for key with `role`
+    fn key(&self, role: &str) -> &Option<String> {
+        match role {
+            "alt" => &self.key_alt,
+            "tab" => &self.key_tab,
+            "shift" => &self.key_shift,
+            "same-app" => &self.key_same_app,
+            "cancel" => &self.key_cancel,
+            _ => &None,
+        }
+    }
+}
+
+/// `Settings` is configuration merged from arguments and configuration file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// source of actions
+    pub input: Source,
+    /// keyboard devices, they are detected if it's empty
+    pub devices: Vec<PathBuf>,
+    /// rules for detecting keyboards
+    pub detection: Detection,
+    /// key sets with different behavior
+    pub bindings: Vec<Keys>,
+    /// windows for switching between
+    pub scope: Scope,
+    /// criteria of windows which are skipped while switching
+    pub exclude: Vec<Criteria>,
+    /// criteria of windows which are pinned at fixed slots
+    pub pin: Vec<Criteria>,
+    /// shell command of helper process which shows windows while switching
+    pub renderer: Option<String>,
+    /// focus selected window on each step of switching
+    pub live_preview: bool,
+    /// highlight selected window instead of focusing it
+    pub highlight: Option<Highlight>,
+}
+
+impl Settings {
+    /// Merge `args` and `config`, arguments which are given explicitly have
+    /// priority
+    pub fn new(args: &ArgMatches<'_>, config: &Config) -> Result<Self, String> {
+        let input = value(args, "input", &config.input).parse()?;
+        let scope = value(args, "scope", &config.scope).parse()?;
+        let devices = values(args, "device", &config.device)
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+
+        let mut keys = Keys::default();
+        for role in Keys::ROLES {
+            let value = value(args, role, config.key(role));
+            keys.set(role, keyboard::parse_keys(value)?)?;
+        }
+        let sets = values(args, "key-set", &config.key_set)
+            .into_iter()
+            .map(|set| Keys::parse(&set, &keys))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let exclude = values(args, "exclude", &config.exclude)
+            .iter()
+            .map(|criteria| criteria.parse())
+            .collect::<Result<_, _>>()?;
+        let pin = values(args, "pin", &config.pin)
+            .iter()
+            .map(|criteria| criteria.parse())
+            .collect::<Result<_, _>>()?;
+
+        let highlight = args
+            .value_of("highlight")
+            .or(config.highlight.as_deref())
+            .map(str::parse)
+            .transpose()?;
+        let renderer = args
+            .value_of("renderer")
+            .map(String::from)
+            .or_else(|| config.renderer.clone());
+
+        let detection = Detection {
+            keys: vec![keys.alt.clone(), keys.tab.clone(), keys.sft.clone()],
+            ignore: values(args, "ignore-device", &config.ignore_device),
+            allow_virtual: args.is_present("allow-virtual") || config.allow_virtual == Some(true),
+        };
+
+        Ok(Self {
+            input,
+            devices,
+            detection,
+            bindings: iter::once(keys).chain(sets).collect(),
+            scope,
+            exclude,
+            pin,
+            renderer,
+            highlight,
+            live_preview: !args.is_present("no-live-preview")
+                && config.no_live_preview != Some(true),
+        })
+    }
+}
+
+/// Get value of argument `name` if it's given explicitly, otherwise value
+/// from configuration file or default value of argument
+fn value<'a>(args: &'a ArgMatches<'_>, name: &str, config: &'a Option<String>) -> &'a str {
+    match config {
+        Some(value) if args.occurrences_of(name) == 0 => value,
+        _ => args.value_of(name).unwrap(),
+    }
+}
+
+/// Get values of argument `name` if they are given, otherwise values from
+/// configuration file
+fn values(args: &ArgMatches<'_>, name: &str, config: &Option<Vec<String>>) -> Vec<String> {
+    match (args.values_of(name), config) {
+        (Some(values), _) => values.map(Into::into).collect(),
+        (None, Some(values)) => values.clone(),
+        (None, None) => vec![],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::{Config, Settings},
+        crate::{app, input::Source, scope::Scope},
+        evdev_rs_tokio::enums::EV_KEY,
+    };
+
+    #[test]
+    fn parse() {
+        let config = Config::parse(
+            r#"
+            scope = "workspace"
+            ignore-device = ["Consumer Control"]
+            allow-virtual = true
+            key-alt = "KEY_LEFTALT,KEY_RIGHTALT"
+            key-set = ["alt=KEY_LEFTMETA scope=output"]
+            exclude = ['[app_id="^mako$"]', "[title=Picture-in-Picture floating]"]
+            "#,
+        );
+
+        assert_eq!(
+            config,
+            Ok(Config {
+                scope: Some("workspace".to_string()),
+                ignore_device: Some(vec!["Consumer Control".to_string()]),
+                allow_virtual: Some(true),
+                key_alt: Some("KEY_LEFTALT,KEY_RIGHTALT".to_string()),
+                key_set: Some(vec!["alt=KEY_LEFTMETA scope=output".to_string()]),
+                exclude: Some(vec![
+                    "[app_id=\"^mako$\"]".to_string(),
+                    "[title=Picture-in-Picture floating]".to_string()
+                ]),
+                ..Config::default()
+            })
+        );
+        assert_eq!(Config::parse(""), Ok(Config::default()));
+        assert!(Config::parse("window = \"all\"").is_err());
+    }
+
+    #[test]
+    fn merge() {
+        let config = Config {
+            scope: Some("workspace".to_string()),
+            input: Some("sway".to_string()),
+            key_alt: Some("KEY_LEFTALT,KEY_RIGHTALT".to_string()),
+            key_set: Some(vec!["alt=KEY_LEFTMETA".to_string()]),
+            ..Config::default()
+        };
+        let args = app::build_app().get_matches_from(["sway-alttab", "--scope", "output"]);
+        let settings = Settings::new(&args, &config).unwrap();
+
+        assert_eq!(settings.scope, Scope::Output);
+        assert_eq!(settings.input, Source::Sway);
+        assert_eq!(settings.bindings.len(), 2);
+        assert_eq!(
+            settings.bindings[0].alt,
+            vec![EV_KEY::KEY_LEFTALT, EV_KEY::KEY_RIGHTALT]
+        );
+        assert_eq!(settings.bindings[0].tab, vec![EV_KEY::KEY_TAB]);
+        assert_eq!(settings.bindings[1].alt, vec![EV_KEY::KEY_LEFTMETA]);
+
+        let args = app::build_app().get_matches_from(["sway-alttab", "--key-set", "tab=KEY_GRAVE"]);
+        let settings = Settings::new(&args, &config).unwrap();
+        assert_eq!(settings.scope, Scope::Workspace);
+        assert_eq!(settings.bindings.len(), 2);
+        assert_eq!(settings.bindings[1].tab, vec![EV_KEY::KEY_GRAVE]);
+        assert!(settings.live_preview);
+
+        let config = Config {
+            no_live_preview: Some(true),
+            ..config
+        };
+        let settings = Settings::new(&args, &config).unwrap();
+        assert!(!settings.live_preview);
+    }
+
+    #[test]
+    fn merge_incorrect() {
+        let config = Config {
+            key_tab: Some("TAB".to_string()),
+            ..Config::default()
+        };
+        let args = app::build_app().get_matches_from(["sway-alttab"]);
+        assert!(Settings::new(&args, &config).is_err());
+
+        let config = Config {
+            exclude: Some(vec!["[pid=1]".to_string()]),
+            ..Config::default()
+        };
+        assert!(Settings::new(&args, &config).is_err());
+    }
+}
@@ -0,0 +1,282 @@
+use {
+    crate::input::Action,
+    nix::{
+        sys::stat::{umask, Mode},
+        unistd::getuid,
+    },
+    serde::Serialize,
+    std::{env, io::ErrorKind, path::PathBuf, str::FromStr, time::Duration},
+    tokio::{
+        fs,
+        io::{self, AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
+        net::{UnixListener, UnixStream},
+        sync::{mpsc, oneshot},
+        time,
+    },
+};
+
+/// Last line of successful response
+const RESPONSE_OK: &str = "ok";
+/// Prefix of last line of failed response
+const RESPONSE_ERROR: &str = "error ";
+/// Delay after failed accepting of client, e.g. when there are too many open
+/// files
+const ACCEPT_ERROR_DELAY: Duration = Duration::from_millis(100);
+
+/// `Command` is request to the daemon over the control socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// run [`Action`]
+    Action(Action),
+    /// dump windows stack, as JSON with `json`
+    List { json: bool },
+    /// pin focused window at slot or after the last pinned window
+    Pin(Option<usize>),
+    /// unpin focused window
+    Unpin,
+}
+
+/// `Response` is lines of output of [`Command`] or error message
+pub type Response = Result<Vec<String>, String>;
+
+/// `ListEntry` is window in JSON output of [`Command::List`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ListEntry<'a> {
+    pub id: i64,
+    pub title: Option<&'a str>,
+    pub app_id: Option<&'a str>,
+    pub class: Option<&'a str>,
+    pub workspace: Option<&'a str>,
+    pub output: Option<&'a str>,
+    pub focused: bool,
+    pub urgent: bool,
+    /// window is selected by switching which isn't finished yet
+    pub preview: bool,
+    /// milliseconds since the Unix epoch
+    pub focused_at: Option<u64>,
+}
+
+/// `Request` is [`Command`] from client waiting for [`Response`]
+#[derive(Debug)]
+pub struct Request {
+    pub command: Command,
+    pub reply: oneshot::Sender<Response>,
+}
+
+/// Names and descriptions of all commands
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("next", "select next window"),
+    ("prev", "select previous window"),
+    ("next-app", "select next window of focused application"),
+    ("prev-app", "select previous window of focused application"),
+    ("commit", "finish switching and remember selected window"),
+    ("cancel", "cancel switching and focus initial window"),
+    ("list", "print windows in order of last access"),
+    (
+        "pin",
+        "pin focused window at fixed slot in order of switching",
+    ),
+    ("unpin", "unpin focused window"),
+];
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some("list"), None, _) => Ok(Self::List { json: false }),
+            (Some("list"), Some("--json"), None) => Ok(Self::List { json: true }),
+            (Some("pin"), None, _) => Ok(Self::Pin(None)),
+            (Some("pin"), Some(slot), None) => match slot.parse() {
+                Ok(slot) if slot > 0 => Ok(Self::Pin(Some(slot))),
+                _ => Err(format!("incorrect slot {}", slot)),
+            },
+            (Some("unpin"), None, _) => Ok(Self::Unpin),
+            _ => s
+                .parse()
+                .map(Self::Action)
+                .map_err(|_| format!("incorrect command {}", s)),
+        }
+    }
+}
+
+/// Path to the control socket in `$XDG_RUNTIME_DIR`
+///
+/// Without `$XDG_RUNTIME_DIR` the socket is in the temporary directory with
+/// uid in the name, so sockets of different users don't collide
+pub fn socket_path() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join(concat!(env!("CARGO_PKG_NAME"), ".sock")),
+        None => env::temp_dir().join(format!("{}-{}.sock", env!("CARGO_PKG_NAME"), getuid())),
+    }
+}
+
+/// Listen the control socket and receive [`Request`] from clients
+///
+/// Socket left by dead daemon is replaced, but socket of running daemon isn't.
+/// Only the owner can connect to the socket. Errors of accepting clients are
+/// logged and don't stop listening
+pub async fn listen() -> io::Result<mpsc::Receiver<Request>> {
+    let path = socket_path();
+
+    if UnixStream::connect(&path).await.is_ok() {
+        return Err(io::Error::new(
+            ErrorKind::AddrInUse,
+            format!("{} is used by another daemon", path.display()),
+        ));
+    }
+    match fs::remove_file(&path).await {
+        Err(err) if err.kind() != ErrorKind::NotFound => return Err(err),
+        _ => {}
+    }
+
+    // permissions of the socket are set at creation, so nobody can connect
+    // before they are restricted
+    let mask = umask(Mode::S_IRWXG | Mode::S_IRWXO | Mode::S_IXUSR);
+    let listener = UnixListener::bind(&path);
+    umask(mask);
+    let listener = listener?;
+    let (tx, rx) = mpsc::channel(10);
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(serve(stream, tx.clone()));
+                }
+                Err(err) => {
+                    eprintln!("can't accept client of control socket: {}", err);
+                    time::sleep(ACCEPT_ERROR_DELAY).await;
+                }
+            }
+        }
+    });
+
+    Ok(rx)
+}
+
+/// Remove the control socket of stopped daemon
+pub async fn close() {
+    if let Err(err) = fs::remove_file(socket_path()).await {
+        eprintln!("can't remove control socket: {}", err);
+    }
+}
+
+/// Serve commands from one client
+async fn serve(stream: UnixStream, tx: mpsc::Sender<Request>) -> io::Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let response = match line.trim().parse() {
+            Ok(command) => {
+                let (reply, response) = oneshot::channel();
+                if tx.send(Request { command, reply }).await.is_err() {
+                    break;
+                }
+                response
+                    .await
+                    .unwrap_or_else(|_| Err("daemon is stopped".to_string()))
+            }
+            Err(err) => Err(err),
+        };
+
+        let mut out = String::new();
+        match response {
+            Ok(lines) => {
+                lines.iter().for_each(|line| {
+                    out.push_str(line);
+                    out.push('\n');
+                });
+                out.push_str(RESPONSE_OK);
+            }
+            Err(err) => {
+                out.push_str(RESPONSE_ERROR);
+                out.push_str(&err);
+            }
+        }
+        out.push('\n');
+        write.write_all(out.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+/// Send `command` to the daemon and print its response
+///
+/// Returns error message from the daemon
+pub async fn send(command: &str) -> io::Result<Result<(), String>> {
+    let stream = UnixStream::connect(socket_path()).await?;
+    let (read, mut write) = stream.into_split();
+
+    write.write_all(format!("{}\n", command).as_bytes()).await?;
+    write.shutdown().await?;
+
+    let mut lines = BufReader::new(read).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line == RESPONSE_OK {
+            return Ok(Ok(()));
+        } else if let Some(err) = line.strip_prefix(RESPONSE_ERROR) {
+            return Ok(Err(err.to_string()));
+        }
+        println!("{}", line);
+    }
+
+    Err(ErrorKind::UnexpectedEof.into())
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::{Command, ListEntry, COMMANDS},
+        crate::input::Action,
+    };
+
+    #[test]
+    fn parse() {
+        assert_eq!("list".parse(), Ok(Command::List { json: false }));
+        assert_eq!("list --json".parse(), Ok(Command::List { json: true }));
+        assert!("list --yaml".parse::<Command>().is_err());
+        assert_eq!("next".parse(), Ok(Command::Action(Action::Next)));
+        assert_eq!("cancel".parse(), Ok(Command::Action(Action::Cancel)));
+        assert!("jump".parse::<Command>().is_err());
+        assert_eq!("pin".parse(), Ok(Command::Pin(None)));
+        assert_eq!("pin 3".parse(), Ok(Command::Pin(Some(3))));
+        assert!("pin 0".parse::<Command>().is_err());
+        assert!("pin x".parse::<Command>().is_err());
+        assert!("pin 1 2".parse::<Command>().is_err());
+        assert_eq!("unpin".parse(), Ok(Command::Unpin));
+    }
+
+    #[test]
+    fn list_entry() {
+        let entry = ListEntry {
+            id: 7,
+            title: Some("vim"),
+            app_id: Some("foot"),
+            class: None,
+            workspace: Some("1"),
+            output: Some("DP-1"),
+            focused: true,
+            urgent: false,
+            preview: false,
+            focused_at: Some(1000),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&entry).unwrap(),
+            concat!(
+                r#"{"id":7,"title":"vim","app_id":"foot","class":null,"workspace":"1","#,
+                r#""output":"DP-1","focused":true,"urgent":false,"preview":false,"focused_at":1000}"#
+            )
+        );
+    }
+
+    #[test]
+    fn parse_all() {
+        COMMANDS.iter().for_each(|(name, _)| {
+            assert!(name.parse::<Command>().is_ok(), "{}", name);
+        });
+    }
+}
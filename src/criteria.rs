@@ -0,0 +1,198 @@
+use {
+    regex::Regex,
+    std::{fmt, str::FromStr},
+};
+
+/// `Window` is properties of window for matching with [`Criteria`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Window<'a> {
+    pub app_id: Option<&'a str>,
+    pub class: Option<&'a str>,
+    pub title: Option<&'a str>,
+    pub workspace: Option<&'a str>,
+    pub marks: &'a [String],
+    pub floating: bool,
+}
+
+/// `Criteria` is sway-style criteria of windows like
+/// `[app_id="^mako$" floating]`
+///
+/// Supported attributes are `app_id`, `class`, `title`, `workspace` and
+/// `con_mark` with regular expressions and `floating` and `tiling` without
+/// values
+#[derive(Debug, Clone, Default)]
+pub struct Criteria {
+    source: String,
+    app_id: Option<Regex>,
+    class: Option<Regex>,
+    title: Option<Regex>,
+    workspace: Option<Regex>,
+    con_mark: Option<Regex>,
+    floating: Option<bool>,
+}
+
+impl Criteria {
+    /// Check if `window` matches all attributes of criteria
+    pub fn matches(&self, window: &Window<'_>) -> bool {
+        let is_match = |regex: &Option<Regex>, value: Option<&str>| match regex {
+            Some(regex) => value.map(|value| regex.is_match(value)).unwrap_or(false),
+            None => true,
+        };
+
+        is_match(&self.app_id, window.app_id)
+            && is_match(&self.class, window.class)
+            && is_match(&self.title, window.title)
+            && is_match(&self.workspace, window.workspace)
+            && self
+                .con_mark
+                .iter()
+                .all(|regex| window.marks.iter().any(|mark| regex.is_match(mark)))
+            && self
+                .floating
+                .iter()
+                .all(|&floating| floating == window.floating)
+    }
+}
+
+/// Split criteria `s` to attributes with optional values
+fn attributes(s: &str) -> Result<Vec<(String, Option<String>)>, String> {
+    let mut attributes = vec![];
+    let mut chars = s.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return Ok(attributes);
+        }
+
+        let mut name = String::new();
+        while let Some(c) = chars.next_if(|&c| c != '=' && !c.is_whitespace()) {
+            name.push(c);
+        }
+
+        if chars.next_if_eq(&'=').is_none() {
+            attributes.push((name, None));
+            continue;
+        }
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') if chars.next_if_eq(&'"').is_some() => value.push('"'),
+                    Some(c) => value.push(c),
+                    None => return Err(format!("unclosed quote in criteria {}", s)),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(c);
+            }
+        }
+        attributes.push((name, Some(value)));
+    }
+}
+
+impl FromStr for Criteria {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let inner = s.trim();
+        let inner = inner
+            .strip_prefix('[')
+            .and_then(|inner| inner.strip_suffix(']'))
+            .unwrap_or(inner);
+
+        let mut criteria = Self {
+            source: s.to_string(),
+            ..Self::default()
+        };
+
+        for (name, value) in attributes(inner)? {
+            let regex = || match &value {
+                Some(value) => Regex::new(value).map(Some).map_err(|err| err.to_string()),
+                None => Err(format!("{} requires value in criteria {}", name, s)),
+            };
+            match (name.as_str(), &value) {
+                ("app_id", _) => criteria.app_id = regex()?,
+                ("class", _) => criteria.class = regex()?,
+                ("title", _) => criteria.title = regex()?,
+                ("workspace", _) => criteria.workspace = regex()?,
+                ("con_mark", _) => criteria.con_mark = regex()?,
+                ("floating", None) => criteria.floating = Some(true),
+                ("tiling", None) => criteria.floating = Some(false),
+                _ => return Err(format!("incorrect attribute {} in criteria {}", name, s)),
+            }
+        }
+
+        Ok(criteria)
+    }
+}
+
+impl PartialEq for Criteria {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl fmt::Display for Criteria {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Criteria, Window};
+
+    fn window() -> Window<'static> {
+        Window {
+            app_id: Some("firefox"),
+            title: Some("Picture-in-Picture"),
+            workspace: Some("2"),
+            floating: true,
+            ..Window::default()
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert!("[app_id=\"firefox\" floating]".parse::<Criteria>().is_ok());
+        assert!("app_id=firefox title=\"a b\"".parse::<Criteria>().is_ok());
+        assert!("[]".parse::<Criteria>().is_ok());
+        assert!("[app_id]".parse::<Criteria>().is_err());
+        assert!("[floating=yes]".parse::<Criteria>().is_err());
+        assert!("[pid=1]".parse::<Criteria>().is_err());
+        assert!("[title=\"(\"]".parse::<Criteria>().is_err());
+        assert!("[title=\"abc]".parse::<Criteria>().is_err());
+        assert!(r#"[title="a\"b"]"#.parse::<Criteria>().is_ok());
+        assert!(r#"[title="a\"]"#.parse::<Criteria>().is_err());
+    }
+
+    #[test]
+    fn matches() {
+        let criteria = |s: &str| s.parse::<Criteria>().unwrap();
+        let window = window();
+
+        assert!(criteria("[app_id=\"^firefox$\" title=\"Picture\" floating]").matches(&window));
+        assert!(criteria("[workspace=2]").matches(&window));
+        assert!(!criteria("[app_id=firefox tiling]").matches(&window));
+        assert!(!criteria("[class=firefox]").matches(&window));
+        assert!(!criteria("[con_mark=scratch]").matches(&window));
+
+        let marks = ["dashboard".to_string()];
+        let marked = Window {
+            marks: &marks,
+            ..window
+        };
+        assert!(criteria("[con_mark=^dash]").matches(&marked));
+
+        let quoted = Window {
+            title: Some("say \"hi\""),
+            ..window
+        };
+        assert!(criteria(r#"[title="\"hi\"$"]"#).matches(&quoted));
+        assert!(!criteria(r#"[title="\"hi\"$"]"#).matches(&window));
+    }
+}
@@ -0,0 +1,111 @@
+use std::str::FromStr;
+
+/// Mark of highlighted window, sway doesn't show marks starting with `_`
+pub const MARK: &str = "_alttab_preview";
+/// Width of border of highlighted window
+pub const BORDER_WIDTH: i32 = 4;
+
+/// `Highlight` is way to show selected window without focusing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    /// add [`MARK`] to window
+    Mark,
+    /// set border of [`BORDER_WIDTH`] pixels, initial border is remembered in
+    /// the mark
+    Border,
+}
+
+impl Highlight {
+    /// Names of all ways of highlighting
+    pub const NAMES: &'static [&'static str] = &["mark", "border"];
+
+    /// Get mark of highlighted window which had `border` of `width` pixels
+    pub fn mark(self, border: &str, width: i32) -> String {
+        match self {
+            Self::Mark => MARK.to_string(),
+            Self::Border => format!("{}:{}:{}", MARK, border, width),
+        }
+    }
+
+    /// Check if `mark` is made by this way of highlighting
+    pub fn matches(self, mark: &str) -> bool {
+        match self {
+            Self::Mark => mark == MARK,
+            Self::Border => restore(mark).is_some(),
+        }
+    }
+
+    /// Get sway command which highlights window
+    pub fn command(self) -> Option<String> {
+        match self {
+            Self::Mark => None,
+            Self::Border => Some(format!("border pixel {}", BORDER_WIDTH)),
+        }
+    }
+}
+
+/// Check if `mark` is made by [`Highlight`]
+pub fn is_highlight(mark: &str) -> bool {
+    matches!(mark.strip_prefix(MARK), Some(rest) if rest.is_empty() || rest.starts_with(':'))
+}
+
+/// Get sway command which restores border remembered in `mark` of
+/// highlighted window
+pub fn restore(mark: &str) -> Option<String> {
+    let (border, width) = mark
+        .strip_prefix(MARK)?
+        .strip_prefix(':')?
+        .split_once(':')?;
+    let width: i32 = width.parse().ok()?;
+    match border {
+        "normal" | "pixel" => Some(format!("border {} {}", border, width)),
+        "none" | "csd" => Some(format!("border {}", border)),
+        _ => None,
+    }
+}
+
+impl FromStr for Highlight {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mark" => Ok(Self::Mark),
+            "border" => Ok(Self::Border),
+            _ => Err(format!("incorrect highlight {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_highlight, restore, Highlight};
+
+    #[test]
+    fn marks() {
+        let mark = Highlight::Border.mark("normal", 2);
+
+        assert_eq!(mark, "_alttab_preview:normal:2");
+        assert!(is_highlight(&mark));
+        assert!(is_highlight(&Highlight::Mark.mark("normal", 2)));
+        assert!(!is_highlight("_alttab_previews"));
+        assert!(!is_highlight("dashboard"));
+        assert!(Highlight::Border.matches(&mark));
+        assert!(!Highlight::Mark.matches(&mark));
+        assert!(Highlight::Mark.matches("_alttab_preview"));
+    }
+
+    #[test]
+    fn restore_border() {
+        let restore_mark = |border, width| restore(&Highlight::Border.mark(border, width));
+
+        assert_eq!(
+            restore_mark("normal", 2),
+            Some("border normal 2".to_string())
+        );
+        assert_eq!(restore_mark("pixel", 1), Some("border pixel 1".to_string()));
+        assert_eq!(restore_mark("none", 0), Some("border none".to_string()));
+        assert_eq!(restore(&Highlight::Mark.mark("normal", 2)), None);
+        assert_eq!(restore("_alttab_preview:thick:2"), None);
+        assert_eq!(restore("_alttab_preview:pixel:x"), None);
+    }
+}
@@ -0,0 +1,120 @@
+use {
+    crate::{
+        keyboard::{self, Detection, DeviceEvent},
+        try_open_file,
+    },
+    futures_util::{pin_mut, StreamExt as _},
+    nix::{
+        errno::Errno,
+        sys::inotify::{AddWatchFlags, InitFlags, Inotify},
+    },
+    std::{
+        collections::HashSet,
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex},
+    },
+    tokio::{
+        io::{self, unix::AsyncFd},
+        sync::mpsc,
+    },
+};
+
+/// Directory with event devices
+const DEV_INPUT: &str = "/dev/input";
+/// Directory with event devices in sysfs
+const SYS_CLASS_INPUT: &str = "/sys/class/input";
+
+/// `Readers` is set of keyboard devices which are read into one channel
+#[derive(Debug, Clone)]
+pub struct Readers {
+    tx: mpsc::Sender<DeviceEvent>,
+    /// paths of devices which are read now
+    opened: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl Readers {
+    /// Create new [`Readers`] sending events to `tx`
+    pub fn new(tx: mpsc::Sender<DeviceEvent>) -> Self {
+        Self {
+            tx,
+            opened: Arc::default(),
+        }
+    }
+
+    /// Start reading device at `path` if it isn't read yet
+    ///
+    /// Device is dropped after error of reading, its keys are released
+    pub async fn open(&self, path: &Path) -> io::Result<()> {
+        if !self.opened.lock().unwrap().insert(path.to_owned()) {
+            return Ok(());
+        }
+
+        let file = match try_open_file(path).await {
+            Ok(file) => file,
+            Err(err) => {
+                self.opened.lock().unwrap().remove(path);
+                return Err(err);
+            }
+        };
+
+        let stream = keyboard::new_stream(file).await?;
+        let tx = self.tx.clone();
+        let opened = self.opened.clone();
+        let path = path.to_owned();
+
+        tokio::spawn(async move {
+            pin_mut!(stream);
+            while let Some(ev) = stream.next().await {
+                let err = match ev {
+                    Ok(ev) => match tx.send((path.clone(), Ok(ev))).await {
+                        Ok(()) => continue,
+                        Err(_) => break,
+                    },
+                    Err(err) => err,
+                };
+                if err.raw_os_error() != Some(Errno::ENODEV as i32) {
+                    eprintln!("can't read {}: {}", path.display(), err);
+                }
+                for ev in keyboard::detached().iter().cloned() {
+                    let _ = tx.send((path.clone(), Ok(ev))).await;
+                }
+                break;
+            }
+            opened.lock().unwrap().remove(&path);
+        });
+
+        Ok(())
+    }
+
+    /// Watch `/dev/input/` and start reading new devices accepted by
+    /// `detection`
+    ///
+    /// Device can be created before it gets permissions, so changes of
+    /// attributes are watched too
+    pub async fn watch(self, detection: Detection) -> io::Result<()> {
+        let inotify = Inotify::init(InitFlags::IN_NONBLOCK | InitFlags::IN_CLOEXEC)?;
+        inotify.add_watch(
+            DEV_INPUT,
+            AddWatchFlags::IN_CREATE | AddWatchFlags::IN_ATTRIB,
+        )?;
+        let inotify = AsyncFd::new(inotify)?;
+
+        loop {
+            let mut guard = inotify.readable().await?;
+            let events = match guard.try_io(|inotify| Ok(inotify.get_ref().read_events()?)) {
+                Ok(events) => events?,
+                Err(_would_block) => continue,
+            };
+
+            for name in events.into_iter().filter_map(|event| event.name) {
+                let sys_path = Path::new(SYS_CLASS_INPUT).join(&name);
+                if let Ok(Some(candidate)) = detection.check(&sys_path).await {
+                    if candidate.rejected.is_none() {
+                        // device without permissions will be opened after change of them
+                        self.open(&candidate.path).await.ok();
+                    }
+                }
+            }
+        }
+    }
+}
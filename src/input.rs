@@ -0,0 +1,88 @@
+use {
+    crate::{scope::Scope, target::Target},
+    futures_core::Stream,
+    std::{pin::Pin, str::FromStr},
+    swayipc_async::Fallible,
+};
+
+/// `Action` is command for switching between windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// select next window
+    Next,
+    /// select previous window
+    Prev,
+    /// select next window of focused application
+    NextApp,
+    /// select previous window of focused application
+    PrevApp,
+    /// finish switching and remember selected window
+    Commit,
+    /// cancel switching and focus initial window
+    Cancel,
+}
+
+/// `Input` is [`Action`] with scope and target of switching
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Input {
+    pub action: Action,
+    /// scope of switching, scope from arguments if `None`
+    pub scope: Option<Scope>,
+    /// things for switching between
+    pub target: Target,
+}
+
+impl From<Action> for Input {
+    fn from(action: Action) -> Self {
+        Self {
+            action,
+            scope: None,
+            target: Target::Window,
+        }
+    }
+}
+
+/// `Actions` is stream of [`Input`] from any [`Source`]
+pub type Actions = Pin<Box<dyn Stream<Item = Fallible<Input>>>>;
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "next" => Ok(Self::Next),
+            "prev" => Ok(Self::Prev),
+            "next-app" => Ok(Self::NextApp),
+            "prev-app" => Ok(Self::PrevApp),
+            "commit" => Ok(Self::Commit),
+            "cancel" => Ok(Self::Cancel),
+            _ => Err(format!("incorrect action {}", s)),
+        }
+    }
+}
+
+/// `Source` is source of [`Action`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// keys from keyboard devices in `/dev/input/`
+    Keyboard,
+    /// `nop sway-alttab <action>` commands from sway bindings
+    Sway,
+}
+
+impl Source {
+    /// Names of all sources
+    pub const NAMES: &'static [&'static str] = &["keyboard", "sway"];
+}
+
+impl FromStr for Source {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keyboard" => Ok(Self::Keyboard),
+            "sway" => Ok(Self::Sway),
+            _ => Err(format!("incorrect input {}", s)),
+        }
+    }
+}
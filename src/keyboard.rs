@@ -1,36 +1,341 @@
 use {
+    crate::{
+        input::{Action, Actions, Input},
+        scope::Scope,
+        target::Target,
+    },
     async_stream::try_stream,
-    evdev_rs_tokio::{enums::EventType, util::int_to_event_code, InputEvent, TimeVal},
+    evdev_rs_tokio::{
+        enums::{EventCode, EventType, EV_KEY, EV_SYN},
+        util::{event_code_to_int, int_to_event_code},
+        InputEvent, TimeVal,
+    },
     futures_core::Stream,
-    nix::libc::input_event,
-    std::{mem, path::Path, str},
+    futures_util::FutureExt as _,
+    nix::libc::{c_ulong, input_event},
+    std::{
+        collections::{HashMap, HashSet},
+        mem,
+        os::unix::io::{AsRawFd as _, RawFd},
+        path::{Path, PathBuf},
+        str::FromStr,
+    },
     tokio::{
         fs::{self, File},
         io::{self, AsyncReadExt as _},
+        sync::{mpsc, watch},
     },
 };
 
 /// Size of [`input_event`] from system
 const INPUT_EVENT_SIZE: usize = mem::size_of::<input_event>();
+/// Size of bitmap with state of all keys
+const KEY_STATE_SIZE: usize = EV_KEY::KEY_MAX as usize / 8 + 1;
+
+nix::ioctl_read_buf!(eviocgkey, b'E', 0x18, u8);
+
+/// `Keys` is set of keys with special behavior
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Keys {
+    /// keys with Tab behavior
+    pub tab: Vec<EV_KEY>,
+    /// keys with Alt behavior
+    pub alt: Vec<EV_KEY>,
+    /// keys with Shift behavior
+    pub sft: Vec<EV_KEY>,
+    /// keys with Tab behavior for windows of focused application
+    pub app: Vec<EV_KEY>,
+    /// keys for cancel switching
+    pub cancel: Vec<EV_KEY>,
+    /// scope of switching, scope from arguments if `None`
+    pub scope: Option<Scope>,
+    /// things for switching between, windows if `None`
+    pub target: Option<Target>,
+}
+
+impl Keys {
+    /// Names of all roles of keys
+    pub const ROLES: &'static [&'static str] = &["alt", "tab", "shift", "same-app", "cancel"];
+
+    /// Set `keys` for `role`
+    pub fn set(&mut self, role: &str, keys: Vec<EV_KEY>) -> Result<(), String> {
+        match role {
+            "alt" => self.alt = keys,
+            "tab" => self.tab = keys,
+            "shift" => self.sft = keys,
+            "same-app" => self.app = keys,
+            "cancel" => self.cancel = keys,
+            _ => return Err(format!("incorrect role {}", role)),
+        }
+        Ok(())
+    }
+
+    /// Parse key set like `alt=KEY_LEFTMETA tab=KEY_TAB scope=workspace`
+    /// or `alt=KEY_LEFTMETA target=workspace`
+    ///
+    /// Keys of missed roles are taken from `default`
+    pub fn parse(s: &str, default: &Keys) -> Result<Self, String> {
+        let mut keys = Self {
+            scope: None,
+            ..default.clone()
+        };
+        for item in s.split_whitespace() {
+            let (name, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("incorrect key set {}", s))?;
+            match name {
+                "scope" => keys.scope = Some(value.parse()?),
+                "target" => keys.target = Some(value.parse()?),
+                role => keys.set(role, parse_keys(value)?)?,
+            }
+        }
+        Ok(keys)
+    }
+}
+
+/// Parse comma separated list of keys like `KEY_LEFTALT,KEY_RIGHTALT`
+pub fn parse_keys(s: &str) -> Result<Vec<EV_KEY>, String> {
+    s.split(',')
+        .map(|key| EV_KEY::from_str(key).map_err(|_| format!("incorrect key {}", key)))
+        .collect()
+}
+
+/// `DeviceEvent` is event from keyboard device at path
+pub type DeviceEvent = (PathBuf, io::Result<InputEvent>);
+
+/// `Device` is state of keyboard device
+#[derive(Debug, Default)]
+struct Device {
+    /// pressed keys
+    pressed: HashSet<EV_KEY>,
+    /// keys collected after [`EV_SYN::SYN_DROPPED`] until [`EV_SYN::SYN_REPORT`]
+    resync: Option<HashSet<EV_KEY>>,
+}
+
+/// `Keyboard` is translator of keyboard events to [`Action`]
+#[derive(Debug)]
+struct Keyboard {
+    /// key sets with different behavior
+    bindings: Vec<Keys>,
+    devices: HashMap<PathBuf, Device>,
+    /// index of key set of current switching
+    active: Option<usize>,
+}
+
+impl Keyboard {
+    /// Create new [`Keyboard`] with key sets `bindings`
+    fn new(bindings: Vec<Keys>) -> Self {
+        Self {
+            bindings,
+            devices: HashMap::new(),
+            active: None,
+        }
+    }
+
+    /// Replace key sets with `bindings`
+    ///
+    /// Current switching continues if its key set still exists
+    fn set_bindings(&mut self, bindings: Vec<Keys>) {
+        self.active = self.active.filter(|&index| index < bindings.len());
+        self.bindings = bindings;
+    }
+
+    /// Any of `keys` is pressed on any device
+    fn pressed(&self, keys: &[EV_KEY]) -> bool {
+        self.devices
+            .values()
+            .any(|device| keys.iter().any(|key| device.pressed.contains(key)))
+    }
+
+    /// Scope of current switching
+    fn scope(&self) -> Option<Scope> {
+        self.active.and_then(|index| self.bindings[index].scope)
+    }
+
+    /// Target of current switching
+    fn target(&self) -> Option<Target> {
+        self.active.and_then(|index| self.bindings[index].target)
+    }
+
+    /// Process keyboard event [`InputEvent`] from `device`
+    fn process(&mut self, device: &Path, event: &InputEvent) -> Option<Action> {
+        let state = self.devices.entry(device.to_owned()).or_default();
+
+        let key = match event.event_code {
+            EventCode::EV_KEY(key) => key,
+            EventCode::EV_SYN(EV_SYN::SYN_DROPPED) => {
+                state.resync = Some(HashSet::new());
+                return None;
+            }
+            EventCode::EV_SYN(EV_SYN::SYN_REPORT) => {
+                state.pressed = state.resync.take()?;
+                return self.released();
+            }
+            _ => return None,
+        };
+
+        let pressed = state.resync.as_mut().unwrap_or(&mut state.pressed);
+        if event.value > 0 {
+            pressed.insert(key);
+        } else {
+            pressed.remove(&key);
+        }
+
+        if state.resync.is_some() {
+            return None;
+        } else if event.value == 0 {
+            return self.released();
+        } else if event.value != 1 {
+            return None;
+        }
+
+        let active = self.active;
+        let (index, keys) = self
+            .bindings
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| active.is_none() || active == Some(*index))
+            .filter(|(_, keys)| self.pressed(&keys.alt))
+            .find(|(_, keys)| {
+                keys.tab.contains(&key) || keys.app.contains(&key) || keys.cancel.contains(&key)
+            })?;
+
+        if keys.cancel.contains(&key) {
+            return Some(Action::Cancel);
+        }
+
+        let app = keys.app.contains(&key);
+        let sft = self.pressed(&keys.sft);
+        self.active = Some(index);
+        Some(match (app, sft) {
+            (false, false) => Action::Next,
+            (false, true) => Action::Prev,
+            (true, false) => Action::NextApp,
+            (true, true) => Action::PrevApp,
+        })
+    }
+
+    /// Create [`Action::Commit`] if keys with Alt behavior of current
+    /// switching are released on all devices
+    fn released(&mut self) -> Option<Action> {
+        let index = self.active?;
+        if self.pressed(&self.bindings[index].alt) {
+            return None;
+        }
+
+        self.active = None;
+        Some(Action::Commit)
+    }
+}
+
+/// Create stream of [`Input`] from keyboard `events` with key sets from
+/// `bindings`
+pub fn actions(
+    mut events: mpsc::Receiver<DeviceEvent>,
+    mut bindings: watch::Receiver<Vec<Keys>>,
+) -> Actions {
+    let mut keyboard = Keyboard::new(bindings.borrow().clone());
+    Box::pin(try_stream! {
+        while let Some((device, event)) = events.recv().await {
+            if let Some(Ok(())) = bindings.changed().now_or_never() {
+                keyboard.set_bindings(bindings.borrow().clone());
+            }
+            let scope = keyboard.scope();
+            let target = keyboard.target();
+            if let Some(action) = keyboard.process(&device, &event?) {
+                let scope = keyboard.scope().or(scope);
+                let target = keyboard.target().or(target).unwrap_or(Target::Window);
+                yield Input { action, scope, target };
+            }
+        }
+    })
+}
+
+/// Create events which release all keys of detached device
+pub fn detached() -> [InputEvent; 2] {
+    let time = TimeVal {
+        tv_sec: 0,
+        tv_usec: 0,
+    };
+    [
+        InputEvent {
+            time,
+            event_code: EventCode::EV_SYN(EV_SYN::SYN_DROPPED),
+            value: 0,
+        },
+        InputEvent {
+            time,
+            event_code: EventCode::EV_SYN(EV_SYN::SYN_REPORT),
+            value: 0,
+        },
+    ]
+}
 
 /// Create asynchronous event stream from `file`
-pub async fn new_stream(file: File) -> io::Result<impl Stream<Item = io::Result<InputEvent>>> {
-    let mut file = Box::pin(file);
+///
+/// After [`EV_SYN::SYN_DROPPED`] events are skipped until next
+/// [`EV_SYN::SYN_REPORT`], then state of keys is queried from device. Stream
+/// yields `SYN_DROPPED`, pressed keys with value `2` and `SYN_REPORT`
+pub async fn new_stream(mut file: File) -> io::Result<impl Stream<Item = io::Result<InputEvent>>> {
     Ok(try_stream! {
         loop {
-            let mut buf = [0u8; INPUT_EVENT_SIZE];
-            file.read_exact(&mut buf).await?;
-            if let Some(ev) = input_event_from_buf(buf) {
-                yield ev;
+            let ev = read_event(&mut file).await?;
+            match ev.event_code {
+                EventCode::EV_KEY(_) => yield ev,
+                EventCode::EV_SYN(EV_SYN::SYN_DROPPED) => {
+                    let report = loop {
+                        let ev = read_event(&mut file).await?;
+                        if ev.event_code == EventCode::EV_SYN(EV_SYN::SYN_REPORT) {
+                            break ev;
+                        }
+                    };
+                    let state = key_state(file.as_raw_fd())?;
+                    yield ev;
+                    for ev in pressed_keys(&state, &report.time) {
+                        yield ev;
+                    }
+                    yield report;
+                }
+                _ => {}
             }
         }
     })
 }
 
+/// Read next key or synchronization event from `file`
+async fn read_event(file: &mut File) -> io::Result<InputEvent> {
+    loop {
+        let mut buf = [0u8; INPUT_EVENT_SIZE];
+        file.read_exact(&mut buf).await?;
+        if let Some(ev) = input_event_from_buf(buf) {
+            return Ok(ev);
+        }
+    }
+}
+
+/// Query state of all keys from device `fd`
+fn key_state(fd: RawFd) -> io::Result<[u8; KEY_STATE_SIZE]> {
+    let mut state = [0u8; KEY_STATE_SIZE];
+    unsafe { eviocgkey(fd, &mut state) }.map_err(|err| io::Error::from_raw_os_error(err as i32))?;
+    Ok(state)
+}
+
+/// Create events with value `2` for keys pressed in `state` at `time`
+fn pressed_keys(state: &[u8], time: &TimeVal) -> Vec<InputEvent> {
+    (0..state.len() as u32 * 8)
+        .filter(|&code| state[code as usize / 8] & (1 << (code % 8)) != 0)
+        .map(|code| InputEvent {
+            time: *time,
+            event_code: int_to_event_code(EventType::EV_KEY as u32, code),
+            value: 2,
+        })
+        .collect()
+}
+
 /// Create [`InputEvent`] from byte array
 fn input_event_from_buf(buf: [u8; INPUT_EVENT_SIZE]) -> Option<InputEvent> {
     let ev: input_event = unsafe { mem::transmute(buf) };
-    if ev.type_ != EventType::EV_KEY as u16 {
+    if ev.type_ != EventType::EV_KEY as u16 && ev.type_ != EventType::EV_SYN as u16 {
         return None;
     }
 
@@ -46,38 +351,550 @@ fn input_event_from_buf(buf: [u8; INPUT_EVENT_SIZE]) -> Option<InputEvent> {
     })
 }
 
-/// Try find keyboard device in `/sys/class/input/`
+/// `Detection` is rules for detecting keyboards
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    /// groups of keys where any key of each group must be supported by
+    /// keyboard
+    pub keys: Vec<Vec<EV_KEY>>,
+    /// keyboards with names containing any of these patterns are ignored
+    pub ignore: Vec<String>,
+    /// virtual keyboards (e.g. created via uinput) are accepted
+    pub allow_virtual: bool,
+}
+
+/// `Candidate` is event device checked by [`Detection`]
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    /// path to event device
+    pub path: PathBuf,
+    /// name of device
+    pub name: String,
+    /// reason of rejecting device
+    pub rejected: Option<String>,
+}
+
+/// Check if `bit` is set in bitmap from sysfs
 ///
-/// * look at each folder in the `/sys/class/input/`
-/// * look at their key capabilities
-/// * choose with large capabilities
-/// * get path to event device at `/sys/class/input/<eventX>/uevent`
-/// * return opened path at this event device
-pub async fn try_find_keyboard() -> io::Result<Vec<String>> {
-    let mut sys_class = fs::read_dir("/sys/class/input/").await?;
-    let mut out = vec![];
-
-    while let Some(dev) = sys_class.next_entry().await? {
-        let dev_path = dev.path();
-        if !dev_path.is_dir() {
-            continue;
-        }
-        let uevent_path = dev_path.join("uevent");
-        let mut buf = vec![];
-        File::open(uevent_path).await?.read_to_end(&mut buf).await?;
-        let uevent: String = str::from_utf8(&buf).unwrap().into();
-        let lines = uevent.lines();
-        for line in lines {
-            let line: Vec<_> = line.split("=").collect();
-            if line[0] == "DEVNAME" {
-                let devname = Path::new("/dev/").join(line[1]);
-                dbg!(&devname);
-                if let Some(devname) = devname.to_str() {
-                    out.push(devname.to_string());
-                }
+/// Bitmap is hex words of `unsigned long` separated by spaces, the most
+/// significant word is the first
+fn bitmap_has(bitmap: &str, bit: u32) -> bool {
+    let word_bits = (mem::size_of::<c_ulong>() * 8) as u32;
+    bitmap
+        .split_whitespace()
+        .rev()
+        .nth((bit / word_bits) as usize)
+        .and_then(|word| c_ulong::from_str_radix(word, 16).ok())
+        .map(|word| word & (1 << (bit % word_bits)) != 0)
+        .unwrap_or(false)
+}
+
+/// Read text file from sysfs
+async fn read_sys(path: &Path) -> io::Result<String> {
+    let mut buf = vec![];
+    File::open(path).await?.read_to_end(&mut buf).await?;
+    Ok(String::from_utf8_lossy(&buf).trim().to_string())
+}
+
+impl Detection {
+    /// Get reason of rejecting device with `name`, bitmaps of event types `ev`
+    /// and keys `key`
+    fn reject(&self, name: &str, ev: &str, key: &str, is_virtual: bool) -> Option<String> {
+        if !bitmap_has(ev, EventType::EV_KEY as u32) {
+            return Some("no key events".to_string());
+        }
+
+        let missed = self.keys.iter().find(|group| {
+            !group.iter().any(|&k| {
+                let (_, code) = event_code_to_int(&EventCode::EV_KEY(k));
+                bitmap_has(key, code)
+            })
+        });
+        if let Some(missed) = missed {
+            let missed: Vec<_> = missed.iter().map(|k| format!("{:?}", k)).collect();
+            return Some(format!("no {}", missed.join(" or ")));
+        }
+
+        if is_virtual && !self.allow_virtual {
+            return Some("virtual device".to_string());
+        }
+
+        self.ignore
+            .iter()
+            .find(|pattern| name.contains(pattern.as_str()))
+            .map(|pattern| format!("ignored by pattern {:?}", pattern))
+    }
+
+    /// Check event device at `/sys/class/input/<eventX>`
+    ///
+    /// Returns [`None`] if it's not event device. Device with unreadable
+    /// capabilities is rejected
+    pub async fn check(&self, sys_path: &Path) -> io::Result<Option<Candidate>> {
+        let is_event = sys_path
+            .file_name()
+            .map(|name| name.to_string_lossy().starts_with("event"))
+            .unwrap_or(false);
+        if !is_event {
+            return Ok(None);
+        }
+
+        let uevent = read_sys(&sys_path.join("uevent")).await?;
+        let devname = uevent
+            .lines()
+            .find_map(|line| line.strip_prefix("DEVNAME="));
+        let path = match devname {
+            Some(devname) => Path::new("/dev/").join(devname),
+            None => return Ok(None),
+        };
+
+        let device = sys_path.join("device");
+        let name = read_sys(&device.join("name")).await.unwrap_or_default();
+        let capabilities = async {
+            let ev = read_sys(&device.join("capabilities/ev")).await?;
+            let key = read_sys(&device.join("capabilities/key")).await?;
+            let is_virtual = fs::canonicalize(sys_path)
+                .await?
+                .to_string_lossy()
+                .contains("/devices/virtual/");
+            Ok::<_, io::Error>((ev, key, is_virtual))
+        };
+
+        let rejected = match capabilities.await {
+            Ok((ev, key, is_virtual)) => self.reject(&name, &ev, &key, is_virtual),
+            Err(err) => Some(format!("can't read capabilities: {}", err)),
+        };
+
+        Ok(Some(Candidate {
+            path,
+            name,
+            rejected,
+        }))
+    }
+
+    /// Check all event devices in `/sys/class/input/`
+    ///
+    /// Devices which can't be checked (e.g. detached while checking) are
+    /// skipped
+    pub async fn candidates(&self) -> io::Result<Vec<Candidate>> {
+        let mut sys_class = fs::read_dir("/sys/class/input/").await?;
+        let mut out = vec![];
+
+        while let Some(dev) = sys_class.next_entry().await? {
+            if let Ok(Some(candidate)) = self.check(&dev.path()).await {
+                out.push(candidate);
             }
         }
+
+        out.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(out)
+    }
+}
+
+/// Try find keyboard devices in `/sys/class/input/`
+///
+/// * look at each event device in the `/sys/class/input/`
+/// * look at their event types and key capabilities
+/// * choose devices with keys from `detection`
+/// * get path to event device at `/sys/class/input/<eventX>/uevent`
+/// * return paths to these event devices
+pub async fn try_find_keyboard(detection: &Detection) -> io::Result<Vec<PathBuf>> {
+    Ok(detection
+        .candidates()
+        .await?
+        .into_iter()
+        .filter(|candidate| candidate.rejected.is_none())
+        .map(|candidate| candidate.path)
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::{bitmap_has, detached, parse_keys, pressed_keys, Detection, Keyboard, Keys},
+        crate::{input::Action, scope::Scope, target::Target},
+        evdev_rs_tokio::{
+            enums::{EventCode, EV_KEY, EV_SYN},
+            util::event_code_to_int,
+            InputEvent, TimeVal,
+        },
+        nix::libc::c_ulong,
+        std::path::Path,
+    };
+
+    fn keys() -> Keys {
+        Keys {
+            tab: vec![EV_KEY::KEY_TAB],
+            alt: vec![EV_KEY::KEY_LEFTALT],
+            sft: vec![EV_KEY::KEY_LEFTSHIFT],
+            app: vec![EV_KEY::KEY_GRAVE],
+            cancel: vec![EV_KEY::KEY_ESC],
+            scope: None,
+            target: None,
+        }
+    }
+
+    fn keyboard() -> Keyboard {
+        Keyboard::new(vec![keys()])
+    }
+
+    fn kbd(n: usize) -> &'static Path {
+        [
+            Path::new("/dev/input/event0"),
+            Path::new("/dev/input/event1"),
+        ][n]
+    }
+
+    fn key(key: EV_KEY, value: i32) -> InputEvent {
+        InputEvent {
+            time: TimeVal {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+            event_code: EventCode::EV_KEY(key),
+            value,
+        }
     }
 
-    Ok(out)
+    fn syn(syn: EV_SYN) -> InputEvent {
+        InputEvent {
+            time: TimeVal {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+            event_code: EventCode::EV_SYN(syn),
+            value: 0,
+        }
+    }
+
+    #[test]
+    fn alt_tab() {
+        let mut kb = keyboard();
+
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 1)), None);
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 1)), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 1)),
+            Some(Action::Next)
+        );
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 2)), None);
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 0)), None);
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTSHIFT, 1)), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 1)),
+            Some(Action::Prev)
+        );
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_GRAVE, 1)),
+            Some(Action::PrevApp)
+        );
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTSHIFT, 0)), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_GRAVE, 1)),
+            Some(Action::NextApp)
+        );
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 0)),
+            Some(Action::Commit)
+        );
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 1)), None);
+    }
+
+    #[test]
+    fn cancel() {
+        let mut kb = keyboard();
+
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_ESC, 1)), None);
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 1)), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 1)),
+            Some(Action::Next)
+        );
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_ESC, 1)),
+            Some(Action::Cancel)
+        );
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_ESC, 0)), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 0)),
+            Some(Action::Commit)
+        );
+    }
+
+    #[test]
+    fn alt_keys() {
+        let mut kb = Keyboard::new(vec![Keys {
+            alt: vec![EV_KEY::KEY_LEFTALT, EV_KEY::KEY_RIGHTALT],
+            ..keys()
+        }]);
+
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_RIGHTALT, 1)), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 1)),
+            Some(Action::Next)
+        );
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 1)), None);
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_RIGHTALT, 0)), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 0)),
+            Some(Action::Commit)
+        );
+    }
+
+    #[test]
+    fn key_sets() {
+        let mut kb = Keyboard::new(vec![
+            keys(),
+            Keys {
+                alt: vec![EV_KEY::KEY_LEFTMETA],
+                scope: Some(Scope::Workspace),
+                target: Some(Target::Workspace),
+                ..keys()
+            },
+        ]);
+
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTMETA, 1)), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 1)),
+            Some(Action::Next)
+        );
+        assert_eq!(kb.scope(), Some(Scope::Workspace));
+        assert_eq!(kb.target(), Some(Target::Workspace));
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 1)), None);
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 0)), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_LEFTMETA, 0)),
+            Some(Action::Commit)
+        );
+        assert_eq!(kb.scope(), None);
+        assert_eq!(kb.target(), None);
+
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 1)), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 1)),
+            Some(Action::Next)
+        );
+        assert_eq!(kb.scope(), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 0)),
+            Some(Action::Commit)
+        );
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            parse_keys("KEY_LEFTALT,KEY_RIGHTALT"),
+            Ok(vec![EV_KEY::KEY_LEFTALT, EV_KEY::KEY_RIGHTALT])
+        );
+        assert!(parse_keys("KEY_LEFTALT,ALT").is_err());
+
+        assert_eq!(
+            Keys::parse("alt=KEY_LEFTMETA  scope=workspace", &keys()),
+            Ok(Keys {
+                alt: vec![EV_KEY::KEY_LEFTMETA],
+                scope: Some(Scope::Workspace),
+                ..keys()
+            })
+        );
+        assert_eq!(
+            Keys::parse("shift=KEY_LEFTSHIFT,KEY_RIGHTSHIFT", &keys()),
+            Ok(Keys {
+                sft: vec![EV_KEY::KEY_LEFTSHIFT, EV_KEY::KEY_RIGHTSHIFT],
+                ..keys()
+            })
+        );
+        assert!(Keys::parse("alt", &keys()).is_err());
+        assert!(Keys::parse("meta=KEY_LEFTMETA", &keys()).is_err());
+        assert!(Keys::parse("scope=window", &keys()).is_err());
+        assert_eq!(
+            Keys::parse("alt=KEY_LEFTMETA target=workspace", &keys()),
+            Ok(Keys {
+                alt: vec![EV_KEY::KEY_LEFTMETA],
+                target: Some(Target::Workspace),
+                ..keys()
+            })
+        );
+        assert!(Keys::parse("target=all", &keys()).is_err());
+    }
+
+    #[test]
+    fn dropped_release() {
+        let mut kb = keyboard();
+
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 1)), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 1)),
+            Some(Action::Next)
+        );
+        assert_eq!(kb.process(kbd(0), &syn(EV_SYN::SYN_DROPPED)), None);
+        assert_eq!(
+            kb.process(kbd(0), &syn(EV_SYN::SYN_REPORT)),
+            Some(Action::Commit)
+        );
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 1)), None);
+    }
+
+    #[test]
+    fn dropped_held() {
+        let mut kb = keyboard();
+
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 1)), None);
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTSHIFT, 1)), None);
+        assert_eq!(kb.process(kbd(0), &syn(EV_SYN::SYN_DROPPED)), None);
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 2)), None);
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 2)), None);
+        assert_eq!(kb.process(kbd(0), &syn(EV_SYN::SYN_REPORT)), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 1)),
+            Some(Action::Next)
+        );
+    }
+
+    #[test]
+    fn dropped_press() {
+        let mut kb = keyboard();
+
+        assert_eq!(kb.process(kbd(0), &syn(EV_SYN::SYN_DROPPED)), None);
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 2)), None);
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_ESC, 1)), None);
+        assert_eq!(kb.process(kbd(0), &syn(EV_SYN::SYN_REPORT)), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 1)),
+            Some(Action::Next)
+        );
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 0)),
+            Some(Action::Commit)
+        );
+    }
+
+    #[test]
+    fn two_keyboards() {
+        let mut kb = keyboard();
+
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 1)), None);
+        assert_eq!(kb.process(kbd(1), &key(EV_KEY::KEY_LEFTALT, 1)), None);
+        assert_eq!(
+            kb.process(kbd(1), &key(EV_KEY::KEY_TAB, 1)),
+            Some(Action::Next)
+        );
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 0)), None);
+        assert_eq!(kb.process(kbd(1), &key(EV_KEY::KEY_LEFTSHIFT, 1)), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 1)),
+            Some(Action::Prev)
+        );
+        assert_eq!(
+            kb.process(kbd(1), &key(EV_KEY::KEY_LEFTALT, 0)),
+            Some(Action::Commit)
+        );
+    }
+
+    #[test]
+    fn detach() {
+        let mut kb = keyboard();
+
+        assert_eq!(kb.process(kbd(0), &key(EV_KEY::KEY_LEFTALT, 1)), None);
+        assert_eq!(kb.process(kbd(1), &key(EV_KEY::KEY_LEFTALT, 1)), None);
+        let [dropped, report] = detached();
+        assert_eq!(kb.process(kbd(1), &dropped), None);
+        assert_eq!(kb.process(kbd(1), &report), None);
+        assert_eq!(
+            kb.process(kbd(0), &key(EV_KEY::KEY_TAB, 1)),
+            Some(Action::Next)
+        );
+        assert_eq!(kb.process(kbd(0), &dropped), None);
+        assert_eq!(kb.process(kbd(0), &report), Some(Action::Commit));
+    }
+
+    #[test]
+    fn pressed() {
+        let time = TimeVal {
+            tv_sec: 1,
+            tv_usec: 2,
+        };
+        let mut state = [0u8; 16];
+        state[1] = 0x80;
+        state[7] = 0x01;
+
+        let keys: Vec<_> = pressed_keys(&state, &time)
+            .into_iter()
+            .map(|ev| (ev.event_code, ev.value, ev.time))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                (EventCode::EV_KEY(EV_KEY::KEY_TAB), 2, time),
+                (EventCode::EV_KEY(EV_KEY::KEY_LEFTALT), 2, time),
+            ]
+        );
+        assert!(pressed_keys(&[0u8; 16], &time).is_empty());
+    }
+
+    #[test]
+    fn bitmap() {
+        assert!(bitmap_has("2", 1));
+        assert!(!bitmap_has("2", 0));
+        assert!(!bitmap_has("", 1));
+        assert!(bitmap_has("120013", 4));
+        assert!(!bitmap_has("120013", 3));
+
+        let bits = (std::mem::size_of::<c_ulong>() * 8) as u32;
+        assert!(bitmap_has("1 0", bits));
+        assert!(!bitmap_has("1 0", 0));
+        assert!(!bitmap_has("1 0", bits * 2));
+        assert!(bitmap_has("4000 0 0", bits * 2 + 14));
+    }
+
+    /// Create bitmap in format of sysfs with `keys`
+    fn key_bitmap(keys: &[EV_KEY]) -> String {
+        let bits = std::mem::size_of::<c_ulong>() * 8;
+        let mut words = [0 as c_ulong; 8];
+        keys.iter().for_each(|&key| {
+            let (_, code) = event_code_to_int(&EventCode::EV_KEY(key));
+            words[code as usize / bits] |= 1 << (code as usize % bits);
+        });
+        let words: Vec<_> = words.iter().rev().map(|w| format!("{:x}", w)).collect();
+        words.join(" ")
+    }
+
+    #[test]
+    fn reject() {
+        let detection = Detection {
+            keys: vec![
+                vec![EV_KEY::KEY_TAB],
+                vec![EV_KEY::KEY_LEFTALT, EV_KEY::KEY_RIGHTALT],
+                vec![EV_KEY::KEY_LEFTSHIFT],
+            ],
+            ignore: vec!["Consumer".to_string()],
+            allow_virtual: false,
+        };
+        let keyboard = key_bitmap(&[EV_KEY::KEY_TAB, EV_KEY::KEY_LEFTALT, EV_KEY::KEY_LEFTSHIFT]);
+        let without_tab = key_bitmap(&[EV_KEY::KEY_LEFTALT, EV_KEY::KEY_LEFTSHIFT]);
+        let without_alt = key_bitmap(&[EV_KEY::KEY_TAB, EV_KEY::KEY_LEFTSHIFT]);
+        let right_alt = key_bitmap(&[EV_KEY::KEY_TAB, EV_KEY::KEY_RIGHTALT, EV_KEY::KEY_LEFTSHIFT]);
+
+        assert_eq!(detection.reject("Keyboard", "3", &keyboard, false), None);
+        assert_eq!(
+            detection.reject("Keyboard", "1", &keyboard, false),
+            Some("no key events".to_string())
+        );
+        assert_eq!(
+            detection.reject("Keyboard", "3", &without_tab, false),
+            Some("no KEY_TAB".to_string())
+        );
+        assert_eq!(
+            detection.reject("Keyboard", "3", &without_alt, false),
+            Some("no KEY_LEFTALT or KEY_RIGHTALT".to_string())
+        );
+        assert_eq!(detection.reject("Keyboard", "3", &right_alt, false), None);
+        assert_eq!(
+            detection.reject("Keyboard", "3", &keyboard, true),
+            Some("virtual device".to_string())
+        );
+        assert_eq!(
+            detection.reject("Keyboard Consumer Control", "3", &keyboard, false),
+            Some("ignored by pattern \"Consumer\"".to_string())
+        );
+    }
 }
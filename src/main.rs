@@ -1,82 +1,220 @@
 ///! Program for switching between windows on swaywm
 use {
-    evdev_rs_tokio::{
-        enums::{EventCode, EV_KEY},
-        InputEvent,
-    },
-    futures_util::{pin_mut, StreamExt as _},
+    clap::ArgMatches,
+    config::{Config, Settings},
+    control::{Command, ListEntry},
+    criteria::Criteria,
+    futures_util::StreamExt as _,
+    highlight::Highlight,
+    hotplug::Readers,
+    input::{Action, Actions, Input, Source},
+    keyboard::{Detection, Keys},
     nix::unistd::{getgroups, getuid, setgid, setuid, Gid, Uid},
-    stack_holder::StackHolder,
+    renderer::{Item, Message, Renderer},
+    scope::Scope,
+    stack_holder::{StackHolder, FIRST_PIN_SLOT},
+    state::Entry,
     std::{
+        cmp,
+        collections::{HashMap, HashSet},
+        env,
         os::unix::fs::{MetadataExt as _, PermissionsExt as _},
-        path::Path,
-        str::FromStr as _,
+        path::{Path, PathBuf},
+        pin::Pin,
+        time::{Duration, SystemTime, UNIX_EPOCH},
     },
     swayipc_async::{
-        Connection, Error, Event, EventStream, EventType, Fallible, Node, NodeLayout, NodeType,
-        WindowChange,
+        Connection, Error, Event, EventStream, EventType, Fallible, Node, NodeBorder, NodeLayout,
+        NodeType, WindowChange, WorkspaceChange,
+    },
+    target::Target,
+    tokio::{
+        fs, io,
+        net::UnixStream,
+        select,
+        signal::unix::{signal, SignalKind},
+        sync::{mpsc, watch},
+        time,
     },
-    tokio::{fs, io, select, sync::mpsc},
+    window_info::WindowInfo,
 };
 
+/// Delay before first try of reconnection to sway
+const RECONNECT_DELAY: Duration = Duration::from_millis(100);
+/// Maximal delay between tries of reconnection to sway
+const RECONNECT_DELAY_MAX: Duration = Duration::from_secs(5);
+
 mod app;
+mod binding;
+mod config;
+mod control;
+mod criteria;
+mod highlight;
+mod hotplug;
+mod input;
 mod keyboard;
+mod renderer;
+mod scope;
 mod stack;
 mod stack_holder;
+mod state;
+mod target;
+mod window_info;
 
 /// `SwayAlttab` is type with main logic of application
 #[derive(Debug)]
 pub struct SwayAlttab {
-    /// key with Tab behavior
-    key_tab: EV_KEY,
-    /// key with Alt behavior
-    key_alt: EV_KEY,
-    /// key with Shift behavior
-    key_sft: EV_KEY,
-
-    /// key with Alt behavior is pressed
-    psd_alt: bool,
-    /// key with Shift behavior is pressed
-    psd_sft: bool,
-
-    /// windows stack in [`StackHolder`]
+    /// windows stack with [`WindowInfo`] of each window in [`StackHolder`]
     stack_holder: StackHolder,
+    /// workspaces stack in [`StackHolder`]
+    workspace_stack: StackHolder,
+    /// name of each workspace in `workspace_stack`
+    workspace_names: HashMap<i64, String>,
+    /// outputs stack in [`StackHolder`]
+    output_stack: StackHolder,
+    /// name of each output in `output_stack`
+    output_names: HashMap<i64, String>,
+    /// target of switching between workspaces or outputs, focused windows
+    /// and workspaces aren't moved up while it's going
+    preview_target: Option<Target>,
+    /// window focused while workspaces or outputs are previewed, it's moved
+    /// up on commit
+    deferred_focus: Option<i64>,
+    /// workspace focused while workspaces or outputs are previewed, it's
+    /// moved up on commit
+    deferred_workspace: Option<i64>,
+
+    /// windows for switching between
+    scope: Scope,
+    /// focus selected window on each step of switching, otherwise only on
+    /// commit
+    live_preview: bool,
+    /// highlight selected window instead of focusing it
+    highlight: Option<Highlight>,
+    /// output name of each workspace
+    outputs: HashMap<String, String>,
+    /// name of focused workspace
+    focused_workspace: Option<String>,
+    /// criteria of windows which are skipped while switching
+    exclude: Vec<Criteria>,
+    /// windows matching `exclude`
+    excluded: HashSet<i64>,
+    /// criteria of windows which are pinned at slots from [`FIRST_PIN_SLOT`]
+    pin: Vec<Criteria>,
+    /// file with saved order of windows
+    state: Option<PathBuf>,
+    /// helper process which shows windows while switching
+    renderer: Option<Renderer>,
 
     /// ignore `focus` event with this window id (for preview mode)
     ignore_move_up: Option<i64>,
+
+    /// connection to sway for commands (it's opened on demand)
+    sway: Option<Connection>,
 }
 
 impl SwayAlttab {
     /// Create [`SwayAlttab`] object with params
-    /// `key_tab` is key with Tab behavior
-    /// `key_alt` is key with Alt behavior
-    /// `key_sft` is key with Shift behavior
-    pub async fn new(key_tab: EV_KEY, key_alt: EV_KEY, key_sft: EV_KEY) -> Result<Self, Error> {
-        let mut swayalttab = Self {
-            key_tab,
-            key_alt,
-            key_sft,
-
-            psd_alt: false,
-            psd_sft: false,
-
-            stack_holder: StackHolder::new(),
-            ignore_move_up: None,
-        };
+    /// `scope` is windows for switching between
+    /// `exclude` is criteria of windows which are skipped while switching
+    /// `pin` is criteria of windows which are pinned at fixed slots
+    /// `state` is file with order of windows saved by previous run
+    pub async fn new(
+        scope: Scope,
+        exclude: Vec<Criteria>,
+        pin: Vec<Criteria>,
+        state: Option<PathBuf>,
+    ) -> Result<Self, Error> {
+        let mut swayalttab = Self::empty(scope, exclude, pin, state);
 
         swayalttab.refresh_nodes().await?;
+        swayalttab.highlight_window(None).await?;
+        swayalttab.restore_state().await;
 
         Ok(swayalttab)
     }
 
+    /// Create [`SwayAlttab`] without windows, see [`SwayAlttab::new`]
+    fn empty(
+        scope: Scope,
+        exclude: Vec<Criteria>,
+        pin: Vec<Criteria>,
+        state: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            stack_holder: StackHolder::new(),
+            workspace_stack: StackHolder::new(),
+            workspace_names: HashMap::new(),
+            output_stack: StackHolder::new(),
+            output_names: HashMap::new(),
+            preview_target: None,
+            deferred_focus: None,
+            deferred_workspace: None,
+
+            scope,
+            live_preview: true,
+            highlight: None,
+            outputs: HashMap::new(),
+            focused_workspace: None,
+            exclude,
+            excluded: HashSet::new(),
+            pin,
+            state,
+            renderer: None,
+
+            ignore_move_up: None,
+
+            sway: None,
+        }
+    }
+
     /// Try create stream of [`EventStream`]
     pub async fn sway_events() -> Result<EventStream, Error> {
         Connection::new()
             .await?
-            .subscribe(&[EventType::Window])
+            .subscribe(&[
+                EventType::Window,
+                EventType::Workspace,
+                EventType::Output,
+                EventType::Shutdown,
+            ])
             .await
     }
 
+    /// Get connection to sway for commands, open it if needed
+    async fn connection(&mut self) -> Result<&mut Connection, Error> {
+        if self.sway.is_none() {
+            self.sway = Some(Connection::new().await?);
+        }
+        Ok(self.sway.as_mut().unwrap())
+    }
+
+    /// Get tree of sway
+    ///
+    /// Connection is reopened once if it's broken
+    async fn get_tree(&mut self) -> Result<Node, Error> {
+        match self.connection().await?.get_tree().await {
+            Ok(root) => Ok(root),
+            Err(_) => {
+                self.sway = None;
+                self.connection().await?.get_tree().await
+            }
+        }
+    }
+
+    /// Run sway command `cmd`
+    ///
+    /// Connection is reopened once if it's broken
+    async fn run_command(&mut self, cmd: &str) -> Result<Vec<Fallible<()>>, Error> {
+        match self.connection().await?.run_command(cmd).await {
+            Ok(outcome) => Ok(outcome),
+            Err(_) => {
+                self.sway = None;
+                self.connection().await?.run_command(cmd).await
+            }
+        }
+    }
+
     /// Get vector of windows as [`Node`]
     fn nodes(tree: &Node) -> Vec<&Node> {
         match tree.node_type {
@@ -92,10 +230,164 @@ impl SwayAlttab {
         }
     }
 
+    /// Get vector of outputs as [`Node`]
+    fn outputs(tree: &Node) -> Vec<&Node> {
+        match tree.node_type {
+            NodeType::Output => vec![tree],
+            _ => tree.nodes.iter().flat_map(SwayAlttab::outputs).collect(),
+        }
+    }
+
+    /// Get vector of workspaces as [`Node`]
+    fn workspaces(tree: &Node) -> Vec<&Node> {
+        match tree.node_type {
+            NodeType::Workspace => vec![tree],
+            _ => tree.nodes.iter().flat_map(SwayAlttab::workspaces).collect(),
+        }
+    }
+
+    /// Get properties of window `node` on `workspace`
+    fn info(&self, node: &Node, workspace: Option<String>) -> WindowInfo {
+        WindowInfo {
+            title: node.name.clone(),
+            app_id: node.app_id.clone(),
+            class: node
+                .window_properties
+                .as_ref()
+                .and_then(|props| props.class.clone()),
+            pid: node.pid,
+            output: workspace
+                .as_ref()
+                .and_then(|ws| self.outputs.get(ws))
+                .cloned(),
+            workspace,
+            marks: node.marks.clone(),
+            floating: node.node_type == NodeType::FloatingCon,
+            fullscreen: node.fullscreen_mode.unwrap_or(0) != 0,
+            urgent: node.urgent,
+            focused_at: None,
+        }
+    }
+
+    /// Check if window with `info` matches any of `exclude`
+    fn is_excluded(&self, info: &WindowInfo) -> bool {
+        let window = info.window();
+        self.exclude
+            .iter()
+            .any(|criteria| criteria.matches(&window))
+    }
+
+    /// Pin window with `id` if it matches any of `pin` and slot of the
+    /// criteria is free, unpin it if it doesn't match anymore
+    ///
+    /// Slot of criteria with index `i` is `FIRST_PIN_SLOT + i`
+    fn update_pin(&mut self, id: i64) {
+        let window = match self.stack_holder.info(id) {
+            Some(info) => info.window(),
+            None => return,
+        };
+        let matched = self
+            .pin
+            .iter()
+            .position(|criteria| criteria.matches(&window))
+            .map(|index| FIRST_PIN_SLOT + index);
+        self.stack_holder.pin_by_rule(id, matched);
+    }
+
+    /// Refresh [`WindowInfo`] of window with `id`, remember if it's excluded
+    /// and update its pin
+    fn update_window(&mut self, id: i64, info: WindowInfo) {
+        if self.is_excluded(&info) {
+            self.excluded.insert(id);
+        } else {
+            self.excluded.remove(&id);
+        }
+        self.stack_holder.set_info(id, info);
+        self.update_pin(id);
+    }
+
+    /// Refresh [`WindowInfo`] of window `node` from event, its workspace is
+    /// kept
+    fn update_node(&mut self, node: &Node) {
+        let workspace = self
+            .stack_holder
+            .info(node.id)
+            .and_then(|info| info.workspace.clone());
+        let info = self.info(node, workspace);
+        self.update_window(node.id, info);
+    }
+
+    /// Check if `tree` or any of its children is focused
+    fn has_focus(tree: &Node) -> bool {
+        tree.focused
+            || tree
+                .nodes
+                .iter()
+                .chain(tree.floating_nodes.iter())
+                .any(SwayAlttab::has_focus)
+    }
+
+    /// Refresh [`WindowInfo`] of each window and output of each workspace from
+    /// `root`
+    fn update_windows(&mut self, root: &Node) {
+        let workspaces = Self::workspaces(root);
+
+        self.outputs = Self::outputs(root)
+            .into_iter()
+            .filter_map(|output| output.name.as_ref().map(|name| (output, name)))
+            .flat_map(|(output, name)| {
+                Self::workspaces(output)
+                    .into_iter()
+                    .filter_map(|ws| ws.name.clone())
+                    .map(move |ws| (ws, name.clone()))
+            })
+            .collect();
+
+        let windows: Vec<_> = workspaces
+            .iter()
+            .flat_map(|ws| {
+                Self::nodes(ws)
+                    .into_iter()
+                    .map(move |node| (node, ws.name.clone()))
+            })
+            .collect();
+        for (node, workspace) in windows {
+            let info = self.info(node, workspace);
+            self.update_window(node.id, info);
+        }
+
+        self.focused_workspace = workspaces
+            .into_iter()
+            .find(|ws| Self::has_focus(ws))
+            .and_then(|ws| ws.name.clone());
+    }
+
+    /// Replace criteria of skipped windows with `exclude` and criteria of
+    /// pinned windows with `pin`
+    pub async fn set_rules(
+        &mut self,
+        exclude: Vec<Criteria>,
+        pin: Vec<Criteria>,
+    ) -> Result<(), Error> {
+        self.exclude = exclude;
+        self.pin = pin;
+        self.refresh_windows().await
+    }
+
+    /// Refresh data of windows and outputs of workspaces without touching the
+    /// stack
+    async fn refresh_windows(&mut self) -> Result<(), Error> {
+        let root = self.get_tree().await?;
+        self.update_windows(&root);
+        Ok(())
+    }
+
     async fn refresh_nodes(&mut self) -> Result<(), Error> {
-        let mut sway = Connection::new().await?;
-        let root = sway.get_tree().await?;
+        let root = self.get_tree().await?;
         let nodes = Self::nodes(&root);
+        let alive: HashSet<_> = nodes.iter().map(|node| node.id).collect();
+        self.stack_holder.retain(|id| alive.contains(&id));
+        self.excluded.retain(|id| alive.contains(id));
         let size = self.stack_holder.depth();
         nodes.iter().for_each(|node| self.stack_holder.add(node.id));
         if size != self.stack_holder.depth() {
@@ -107,67 +399,740 @@ impl SwayAlttab {
         if let Some(node) = nodes.iter().find(|node| node.focused) {
             self.stack_holder.move_up(node.id);
         }
+        self.update_windows(&root);
+        self.update_workspaces(&root);
+        self.update_outputs(&root);
+        Ok(())
+    }
+
+    /// Refresh outputs and workspaces after connecting or disconnecting of
+    /// outputs
+    async fn refresh_outputs(&mut self) -> Result<(), Error> {
+        let root = self.get_tree().await?;
+        self.update_windows(&root);
+        self.update_workspaces(&root);
+        self.update_outputs(&root);
+        Ok(())
+    }
+
+    /// Refresh stack of workspaces from `root`, focused workspace is moved up
+    fn update_workspaces(&mut self, root: &Node) {
+        let workspaces: Vec<_> = Self::workspaces(root)
+            .into_iter()
+            .filter(|ws| ws.name.as_deref() != Some("__i3_scratch"))
+            .collect();
+
+        self.workspace_names = workspaces
+            .iter()
+            .filter_map(|ws| ws.name.clone().map(|name| (ws.id, name)))
+            .collect();
+
+        let names = &self.workspace_names;
+        self.workspace_stack.retain(|id| names.contains_key(&id));
+        for ws in &workspaces {
+            self.workspace_stack.add(ws.id);
+        }
+        if let Some(ws) = workspaces.into_iter().find(|ws| Self::has_focus(ws)) {
+            self.workspace_stack.move_up(ws.id);
+        }
+    }
+
+    /// Refresh stack of outputs from `root`, focused output is moved up
+    ///
+    /// Disconnected outputs are dropped, connected ones are added to the down
+    fn update_outputs(&mut self, root: &Node) {
+        let outputs: Vec<_> = Self::outputs(root)
+            .into_iter()
+            .filter(|output| output.name.as_deref() != Some("__i3"))
+            .collect();
+
+        self.output_names = outputs
+            .iter()
+            .filter_map(|output| output.name.clone().map(|name| (output.id, name)))
+            .collect();
+
+        let names = &self.output_names;
+        self.output_stack.retain(|id| names.contains_key(&id));
+        for output in &outputs {
+            self.output_stack.add(output.id);
+        }
+        if let Some(output) = outputs.into_iter().find(|output| Self::has_focus(output)) {
+            self.output_stack.move_up(output.id);
+        }
+    }
+
+    /// Check if switching between windows, workspaces or outputs is going
+    fn is_previewing(&self) -> bool {
+        self.preview_target.is_some()
+            || self.ignore_move_up.is_some()
+            || self.stack_holder.preview().is_some()
+    }
+
+    /// Process focusing of workspace with `id` by sway
+    ///
+    /// Workspaces passed while previewing aren't moved up, only the last one
+    /// is remembered till commit
+    fn workspace_focused(&mut self, id: i64) {
+        if self.is_previewing() {
+            self.deferred_workspace = Some(id);
+        } else {
+            self.focus_workspace(id);
+        }
+    }
+
+    /// Process focusing of window with `id` by sway
+    ///
+    /// Window focused by preview isn't moved up
+    fn window_focused(&mut self, id: i64) {
+        if self.preview_target.is_some() {
+            self.deferred_focus = Some(id);
+        } else if self.ignore_move_up != Some(id) {
+            self.stack_holder.move_up(id)
+        } else {
+            self.ignore_move_up = None;
+            // initial window is focused again after cancel
+            if !self.is_previewing() {
+                if let Some(ws) = self.deferred_workspace.take() {
+                    self.focus_workspace(ws);
+                }
+            }
+        }
+        if let Some(info) = self.stack_holder.info_mut(id) {
+            info.focused_at = Some(SystemTime::now());
+            if let Some(workspace) = &info.workspace {
+                self.focused_workspace = Some(workspace.clone());
+            }
+        }
+    }
+
+    /// Remember focusing of workspace with `id` and its output
+    fn focus_workspace(&mut self, id: i64) {
+        self.workspace_stack.move_up(id);
+
+        let output = self
+            .workspace_names
+            .get(&id)
+            .and_then(|ws| self.outputs.get(ws));
+        let output = self
+            .output_names
+            .iter()
+            .find(|&(_, name)| Some(name) == output)
+            .map(|(&id, _)| id);
+        if let Some(id) = output {
+            self.output_stack.move_up(id);
+        }
+    }
+
+    /// Get saved entry of each window in order of the stack
+    fn entries(&self) -> Vec<Entry> {
+        self.stack_holder
+            .windows()
+            .map(|(id, info)| Entry {
+                id,
+                pid: info.pid,
+                app_id: info.app().map(String::from),
+                title: info.title.clone(),
+            })
+            .collect()
+    }
+
+    /// Save order of windows to file from arguments
+    pub async fn save_state(&self) -> io::Result<()> {
+        match &self.state {
+            Some(path) => state::save(path, &self.entries()).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Restore order of windows from file saved by previous run
+    ///
+    /// Windows are matched by id, or by pid, application and title if ids
+    /// are changed. Focused window is kept at the top
+    async fn restore_state(&mut self) {
+        let saved = match &self.state {
+            Some(path) => match state::load(path).await {
+                Ok(saved) => saved,
+                Err(err) => {
+                    eprintln!(
+                        "can't read order of windows from {}: {}",
+                        path.display(),
+                        err
+                    );
+                    return;
+                }
+            },
+            None => return,
+        };
+
+        let focused = self.stack_holder.get(0);
+        let order = state::reconcile(&saved, &self.entries());
+        order
+            .into_iter()
+            .rev()
+            .chain(focused)
+            .for_each(|id| self.stack_holder.move_up(id));
+    }
+
+    /// Save order of windows, failure is only reported
+    async fn try_save_state(&self) {
+        if let Err(err) = self.save_state().await {
+            eprintln!("can't save order of windows: {}", err);
+        }
+    }
+
+    /// Forget state of old sway and take windows from new one
+    ///
+    /// Order of windows with same ids is kept
+    async fn reconnect(&mut self) -> Result<(), Error> {
+        self.sway = None;
+        self.ignore_move_up = None;
+        self.stack_holder.preview_cancel();
+        self.cancel_preview();
+        self.workspace_stack.preview_cancel();
+        self.output_stack.preview_cancel();
+        self.refresh_nodes().await?;
+        self.highlight_window(None).await
+    }
+
+    /// Focus window
+    async fn focus(&mut self, id: i64) -> Result<(), Error> {
+        let cmd = format!("[con_id={}] focus", id);
+        self.run_command(&cmd).await.map(|_| ())
+    }
+
+    /// Get style of border of window `node` for `border` command
+    fn border(node: &Node) -> &'static str {
+        match node.border {
+            NodeBorder::None => "none",
+            NodeBorder::Pixel => "pixel",
+            NodeBorder::Csd => "csd",
+            _ => "normal",
+        }
+    }
+
+    /// Highlight window with `id` if it's given and remove highlighting of
+    /// other windows
+    ///
+    /// Highlighted windows are found by marks, so highlighting left by
+    /// interrupted daemon is removed too
+    async fn highlight_window(&mut self, id: Option<i64>) -> Result<(), Error> {
+        let root = self.get_tree().await?;
+        let nodes = Self::nodes(&root);
+        let highlight = id.and(self.highlight);
+
+        let mut commands = vec![];
+        let mut highlighted = false;
+        for node in &nodes {
+            let marks = node
+                .marks
+                .iter()
+                .filter(|mark| highlight::is_highlight(mark));
+            for mark in marks {
+                if Some(node.id) == id && matches!(highlight, Some(h) if h.matches(mark)) {
+                    highlighted = true;
+                    continue;
+                }
+                if let Some(restore) = highlight::restore(mark) {
+                    commands.push(format!("[con_id={}] {}", node.id, restore));
+                }
+                commands.push(format!("[con_id={}] unmark {}", node.id, Self::quote(mark)));
+            }
+        }
+
+        let node = nodes.iter().find(|node| Some(node.id) == id);
+        if let (Some(node), Some(highlight), false) = (node, highlight, highlighted) {
+            let mark = highlight.mark(Self::border(node), node.current_border_width);
+            commands.push(format!(
+                "[con_id={}] mark --add {}",
+                node.id,
+                Self::quote(&mark)
+            ));
+            if let Some(command) = highlight.command() {
+                commands.push(format!("[con_id={}] {}", node.id, command));
+            }
+        }
+
+        if !commands.is_empty() {
+            self.run_command(&commands.join("; ")).await?;
+        }
         Ok(())
     }
 
     /// Focus window in preview mode
     async fn preview(&mut self, id: i64) -> Result<(), Error> {
-        let mut sway = Connection::new().await?;
-        let cmd = format!("[con_id={}] focus", id);
         self.ignore_move_up = Some(id);
-        let result = sway.run_command(cmd).await;
-        result.map(|_| ()).map_err(|err| {
+        let result = self.focus(id).await;
+        result.map_err(|err| {
             self.ignore_move_up = None;
             err
         })
     }
 
-    /// Process keyboard event [`InputEvent`]
-    pub async fn process_keyboard_event(&mut self, event: InputEvent) -> Result<(), Error> {
-        if let EventCode::EV_KEY(key) = event.event_code {
-            if key == self.key_alt {
-                self.psd_alt = event.value > 0;
-                if !self.psd_alt {
-                    self.stack_holder.preview_finish();
-                    self.ignore_move_up = None;
-                }
-            } else if key == self.key_sft {
-                self.psd_sft = event.value > 0;
-            } else if key == self.key_tab && self.psd_alt && event.value == 1 {
-                let id = if !self.psd_sft {
-                    self.stack_holder.preview_next()
-                } else {
-                    self.stack_holder.preview_prev()
-                };
-                if let Some(id) = id {
-                    self.preview(id).await?;
-                }
+    /// Get place of window on `workspace` which is same for all windows in
+    /// the `scope`
+    fn place_in_scope<'a>(
+        scope: Scope,
+        outputs: &'a HashMap<String, String>,
+        workspace: Option<&'a String>,
+    ) -> Option<&'a String> {
+        match scope {
+            Scope::Output => workspace.and_then(|ws| outputs.get(ws)),
+            _ => workspace,
+        }
+    }
+
+    /// Get windows in the `scope` which aren't excluded, `None` is all windows
+    ///
+    /// With `same_app` only windows of application of the top window are
+    /// taken
+    fn in_scope(&self, scope: Scope, same_app: bool) -> Option<HashSet<i64>> {
+        let stack_holder = &self.stack_holder;
+        let excluded = &self.excluded;
+
+        if scope == Scope::All && !same_app && excluded.is_empty() {
+            return None;
+        }
+
+        let outputs = &self.outputs;
+        let place = |ws| Self::place_in_scope(scope, outputs, ws);
+        let focused = place(self.focused_workspace.as_ref());
+        let app = stack_holder
+            .get(0)
+            .and_then(|id| stack_holder.info(id))
+            .and_then(WindowInfo::app);
+        let in_scope = stack_holder
+            .windows()
+            .filter(|&(id, info)| {
+                !excluded.contains(&id)
+                    && (scope == Scope::All
+                        || focused.is_some() && place(info.workspace.as_ref()) == focused)
+                    && (!same_app || app.is_some() && info.app() == app)
+            })
+            .map(|(id, _)| id)
+            .collect();
+        Some(in_scope)
+    }
+
+    /// Select next (or previous if `backward`) window in the `scope`
+    ///
+    /// With `same_app` only windows of application of the top window are
+    /// selected. Excluded windows are skipped
+    fn preview_step(&mut self, scope: Scope, same_app: bool, backward: bool) -> Option<i64> {
+        let in_scope = self.in_scope(scope, same_app);
+        let stack_holder = &mut self.stack_holder;
+
+        match (in_scope, backward) {
+            (None, false) => stack_holder.preview_next(),
+            (None, true) => stack_holder.preview_prev(),
+            (Some(in_scope), false) => stack_holder.preview_next_by(|id| in_scope.contains(&id)),
+            (Some(in_scope), true) => stack_holder.preview_prev_by(|id| in_scope.contains(&id)),
+        }
+    }
+
+    /// Replace renderer with helper process running shell `command`
+    ///
+    /// Running helper process is kept if the command isn't changed
+    pub fn set_renderer(&mut self, command: Option<String>) {
+        if self.renderer.as_ref().map(Renderer::command) == command.as_deref() {
+            return;
+        }
+
+        self.renderer = command.map(Renderer::new);
+        if let Some(renderer) = &mut self.renderer {
+            if let Err(err) = renderer.start() {
+                eprintln!("can't start renderer: {}", err);
+            }
+        }
+    }
+
+    /// Send windows in the `scope` in order of switching with selected one
+    /// to renderer
+    fn show_windows(&mut self, scope: Scope, same_app: bool) {
+        if self.renderer.is_none() {
+            return;
+        }
+
+        let in_scope = self.in_scope(scope, same_app);
+        let stack_holder = &self.stack_holder;
+        let selected = stack_holder.preview().or_else(|| stack_holder.get(0));
+        let windows: Vec<_> = stack_holder
+            .order()
+            .filter(|id| match &in_scope {
+                Some(in_scope) => in_scope.contains(id),
+                None => true,
+            })
+            .filter_map(|id| stack_holder.info(id).map(|info| (id, info)))
+            .map(|(id, info)| Item {
+                id,
+                title: info.title.as_deref(),
+                app_id: info.app(),
+            })
+            .collect();
+        let selected = windows
+            .iter()
+            .position(|item| Some(item.id) == selected)
+            .unwrap_or(0);
+
+        let message = Message::Show { windows, selected };
+        if let Some(renderer) = &mut self.renderer {
+            if let Err(err) = renderer.send(&message) {
+                eprintln!("renderer failed: {}", err);
             }
         }
+    }
+
+    /// Hide windows shown by renderer
+    fn hide_windows(&mut self) {
+        if let Some(renderer) = &mut self.renderer {
+            if let Err(err) = renderer.send(&Message::Hide) {
+                eprintln!("renderer failed: {}", err);
+            }
+        }
+    }
+
+    /// Process [`Action`] with scope from arguments
+    pub async fn process_action(&mut self, action: Action) -> Result<(), Error> {
+        self.process_input(Input::from(action)).await
+    }
+
+    /// Process [`Input`] from source of actions
+    pub async fn process_input(&mut self, input: Input) -> Result<(), Error> {
+        let scope = input.scope.unwrap_or(self.scope);
+        match input.target {
+            Target::Window => self.process_window_input(input.action, scope).await,
+            Target::Workspace => self.process_workspace_input(input.action, scope).await,
+            Target::Output => self.process_output_input(input.action).await,
+        }
+    }
+
+    /// Process `action` for switching between windows in the `scope`
+    ///
+    /// Without live preview selected window is focused only on commit, it
+    /// can be highlighted instead
+    async fn process_window_input(&mut self, action: Action, scope: Scope) -> Result<(), Error> {
+        let live = self.is_live();
+        let id = match action {
+            Action::Next => self.preview_step(scope, false, false),
+            Action::Prev => self.preview_step(scope, false, true),
+            Action::NextApp => self.preview_step(scope, true, false),
+            Action::PrevApp => self.preview_step(scope, true, true),
+            Action::Commit => {
+                let selected = self.finish_window_preview();
+                self.try_save_state().await;
+                selected.filter(|_| !live)
+            }
+            Action::Cancel => self.cancel_window_preview().filter(|_| live),
+        };
+
+        let finished = action == Action::Commit || action == Action::Cancel;
+        if finished && self.highlight.is_some() {
+            self.highlight_window(None).await?;
+        }
+
+        match id {
+            Some(id) if action == Action::Commit => self.focus(id).await?,
+            Some(id) if live => self.preview(id).await?,
+            Some(id) if self.highlight.is_some() => self.highlight_window(Some(id)).await?,
+            _ => {}
+        }
+
+        match action {
+            Action::Commit | Action::Cancel => self.hide_windows(),
+            Action::Next | Action::Prev => self.show_windows(scope, false),
+            Action::NextApp | Action::PrevApp => self.show_windows(scope, true),
+        }
 
         Ok(())
     }
 
+    /// Finish switching between windows, workspace focused while switching
+    /// is moved up
+    ///
+    /// Returns selected window
+    fn finish_window_preview(&mut self) -> Option<i64> {
+        let selected = self.stack_holder.preview();
+        self.stack_holder.preview_finish();
+        self.ignore_move_up = None;
+        if let Some(id) = self.deferred_workspace.take() {
+            self.focus_workspace(id);
+        }
+        selected
+    }
+
+    /// Cancel switching between windows
+    ///
+    /// Returns initial window for focusing if other window was selected
+    fn cancel_window_preview(&mut self) -> Option<i64> {
+        self.ignore_move_up = None;
+        self.deferred_workspace = None;
+        self.stack_holder.preview_cancel()
+    }
+
+    /// Check if selected window is focused on each step of switching
+    fn is_live(&self) -> bool {
+        self.live_preview && self.highlight.is_none()
+    }
+
+    /// Replace way of highlighting selected window
+    pub async fn set_highlight(&mut self, highlight: Option<Highlight>) -> Result<(), Error> {
+        if self.highlight == highlight {
+            return Ok(());
+        }
+        self.highlight = highlight;
+        self.highlight_window(None).await
+    }
+
+    /// Quote `name` for sway command
+    fn quote(name: &str) -> String {
+        format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    /// Focus workspace with `id` in preview mode
+    async fn preview_workspace(&mut self, id: i64) -> Result<(), Error> {
+        let cmd = match self.workspace_names.get(&id) {
+            Some(name) => format!("workspace --no-auto-back-and-forth {}", Self::quote(name)),
+            None => return Ok(()),
+        };
+        self.run_command(&cmd).await.map(|_| ())
+    }
+
+    /// Focus output with `id` in preview mode
+    async fn preview_output(&mut self, id: i64) -> Result<(), Error> {
+        let cmd = match self.output_names.get(&id) {
+            Some(name) => format!("focus output {}", Self::quote(name)),
+            None => return Ok(()),
+        };
+        self.run_command(&cmd).await.map(|_| ())
+    }
+
+    /// Select next (or previous if `backward`) workspace in the `scope`
+    ///
+    /// With [`Scope::Output`] only workspaces of focused output are selected
+    fn workspace_step(&mut self, scope: Scope, backward: bool) -> Option<i64> {
+        let names = &self.workspace_names;
+        let outputs = &self.outputs;
+        let output = |ws: Option<&String>| ws.and_then(|ws| outputs.get(ws));
+        let focused = output(self.focused_workspace.as_ref());
+        let in_scope =
+            |id| scope != Scope::Output || focused.is_some() && output(names.get(&id)) == focused;
+
+        self.preview_target = Some(Target::Workspace);
+        if !backward {
+            self.workspace_stack.preview_next_by(in_scope)
+        } else {
+            self.workspace_stack.preview_prev_by(in_scope)
+        }
+    }
+
+    /// Select next (or previous if `backward`) output
+    fn output_step(&mut self, backward: bool) -> Option<i64> {
+        self.preview_target = Some(Target::Output);
+        if !backward {
+            self.output_stack.preview_next()
+        } else {
+            self.output_stack.preview_prev()
+        }
+    }
+
+    /// Finish switching between workspaces or outputs
+    ///
+    /// Workspace and window focused while switching are moved up
+    async fn finish_preview(&mut self) {
+        self.preview_target = None;
+        if let Some(id) = self.deferred_workspace.take() {
+            self.focus_workspace(id);
+        }
+        if let Some(id) = self.deferred_focus.take() {
+            self.stack_holder.move_up(id);
+            self.try_save_state().await;
+        }
+    }
+
+    /// Cancel switching between workspaces or outputs
+    fn cancel_preview(&mut self) {
+        self.preview_target = None;
+        self.deferred_focus = None;
+        self.deferred_workspace = None;
+    }
+
+    /// Process `action` for switching between workspaces in the `scope`
+    ///
+    /// Window focused on selected workspace is moved up on commit
+    async fn process_workspace_input(&mut self, action: Action, scope: Scope) -> Result<(), Error> {
+        let live = self.live_preview;
+        let id = match action {
+            Action::Next | Action::NextApp => self.workspace_step(scope, false),
+            Action::Prev | Action::PrevApp => self.workspace_step(scope, true),
+            Action::Commit => {
+                let selected = self.workspace_stack.preview();
+                self.workspace_stack.preview_finish();
+                self.finish_preview().await;
+                selected.filter(|_| !live)
+            }
+            Action::Cancel => {
+                self.cancel_preview();
+                self.workspace_stack.preview_cancel().filter(|_| live)
+            }
+        };
+
+        match id {
+            Some(id) if live || action == Action::Commit => self.preview_workspace(id).await?,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Process `action` for switching between outputs
+    ///
+    /// Workspace and window focused on selected output are moved up on commit
+    async fn process_output_input(&mut self, action: Action) -> Result<(), Error> {
+        let live = self.live_preview;
+        let id = match action {
+            Action::Next | Action::NextApp => self.output_step(false),
+            Action::Prev | Action::PrevApp => self.output_step(true),
+            Action::Commit => {
+                let selected = self.output_stack.preview();
+                self.output_stack.preview_finish();
+                self.finish_preview().await;
+                selected.filter(|_| !live)
+            }
+            Action::Cancel => {
+                self.cancel_preview();
+                self.output_stack.preview_cancel().filter(|_| live)
+            }
+        };
+
+        match id {
+            Some(id) if live || action == Action::Commit => self.preview_output(id).await?,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Get windows in order of the stack with their properties as JSON array
+    fn list_json(&self) -> String {
+        let preview = self.stack_holder.preview();
+        let focused = preview
+            .filter(|_| self.is_live())
+            .or_else(|| self.stack_holder.get(0));
+        let entries: Vec<_> = self
+            .stack_holder
+            .windows()
+            .map(|(id, info)| ListEntry {
+                id,
+                title: info.title.as_deref(),
+                app_id: info.app_id.as_deref(),
+                class: info.class.as_deref(),
+                workspace: info.workspace.as_deref(),
+                output: info.output.as_deref(),
+                focused: Some(id) == focused,
+                urgent: info.urgent,
+                preview: Some(id) == preview,
+                focused_at: info
+                    .focused_at
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|time| time.as_millis() as u64),
+            })
+            .collect();
+        serde_json::to_string(&entries).unwrap()
+    }
+
+    /// Process [`Command`] from control socket
+    pub async fn process_command(&mut self, command: Command) -> Result<Vec<String>, Error> {
+        match command {
+            Command::Action(action) => self.process_action(action).await.map(|_| vec![]),
+            Command::List { json: false } => {
+                Ok(self.stack_holder.iter().map(|id| id.to_string()).collect())
+            }
+            Command::List { json: true } => Ok(vec![self.list_json()]),
+            Command::Pin(slot) => Ok(self
+                .stack_holder
+                .get(0)
+                .map(|id| self.stack_holder.pin(id, slot).to_string())
+                .into_iter()
+                .collect()),
+            Command::Unpin => {
+                if let Some(id) = self.stack_holder.get(0) {
+                    self.stack_holder.unpin(id);
+                }
+                Ok(vec![])
+            }
+        }
+    }
+
     /// Process sway event [`Event`]
     pub async fn process_sway_event(&mut self, event: Event) -> Result<(), Error> {
-        if let Event::Window(window) = event {
-            let id = window.container.id;
-            match window.change {
-                WindowChange::New => self.stack_holder.add(id),
-                WindowChange::Close => self.stack_holder.remove(id),
-                WindowChange::Focus => {
-                    if self.ignore_move_up != Some(id) {
-                        self.stack_holder.move_up(id)
-                    } else {
-                        self.ignore_move_up = None;
+        match event {
+            Event::Window(window) => {
+                self.process_window_event(window.change, &window.container)
+                    .await
+            }
+            Event::Workspace(workspace) => {
+                let current = workspace.current;
+                match workspace.change {
+                    WorkspaceChange::Focus => {
+                        if let Some(ws) = &current {
+                            self.workspace_focused(ws.id);
+                        }
+                        self.focused_workspace = current.and_then(|ws| ws.name);
+                        Ok(())
+                    }
+                    WorkspaceChange::Empty => {
+                        if let Some(ws) = current {
+                            self.workspace_stack.remove(ws.id);
+                            self.workspace_names.remove(&ws.id);
+                        }
+                        Ok(())
+                    }
+                    WorkspaceChange::Init | WorkspaceChange::Rename | WorkspaceChange::Move => {
+                        if let Some(ws) = current {
+                            if let Some(name) = ws.name {
+                                self.workspace_stack.add(ws.id);
+                                self.workspace_names.insert(ws.id, name);
+                            }
+                        }
+                        self.refresh_windows().await
                     }
+                    _ => Ok(()),
                 }
-                WindowChange::Move => {
-                    self.stack_holder.remove(id);
-                    self.refresh_nodes().await?;
+            }
+            Event::Output(_) => self.refresh_outputs().await,
+            _ => Ok(()),
+        }
+    }
+
+    /// Process `change` of window `node`
+    async fn process_window_event(
+        &mut self,
+        change: WindowChange,
+        node: &Node,
+    ) -> Result<(), Error> {
+        let id = node.id;
+        match change {
+            WindowChange::New => {
+                self.stack_holder.add(id);
+                self.refresh_windows().await?;
+            }
+            WindowChange::Close => {
+                self.stack_holder.remove(id);
+                self.excluded.remove(&id);
+            }
+            WindowChange::Title
+            | WindowChange::Mark
+            | WindowChange::Floating
+            | WindowChange::Urgent
+            | WindowChange::FullscreenMode => self.update_node(node),
+            WindowChange::Focus => self.window_focused(id),
+            WindowChange::Move => {
+                // moved window goes up without removing, so its pin and properties are kept
+                if self.stack_holder.info(id).is_some() {
+                    self.stack_holder.move_up(id);
                 }
-                _ => {}
+                self.refresh_nodes().await?;
             }
+            _ => {}
         }
 
         Ok(())
@@ -178,59 +1143,288 @@ impl SwayAlttab {
 async fn main() -> Fallible<()> {
     let args = app::build_app().get_matches_from(std::env::args_os());
 
-    let device = args.values_of("device");
-    let key_alt = args.value_of("alt").unwrap();
-    let key_sft = args.value_of("shift").unwrap();
-    let key_tab = args.value_of("tab").unwrap();
+    if let (command, Some(args)) = args.subcommand() {
+        let mut command = command.to_string();
+        if let Some(slot) = args.value_of("slot") {
+            command = format!("{} {}", command, slot);
+        }
+        if args.is_present("json") {
+            command.push_str(" --json");
+        }
+        if let Err(err) = control::send(&command).await? {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let config_path = args
+        .value_of("config")
+        .map(PathBuf::from)
+        .or_else(Config::default_path);
+    let config = read_config(&args, config_path.as_deref()).await?;
+    let mut settings = Settings::new(&args, &config)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
 
-    let key_error = |key| format!("incorrect key {}", key);
+    if args.is_present("list-devices") {
+        list_devices(&settings.detection).await?;
+        return Ok(());
+    }
 
-    let key_alt = EV_KEY::from_str(key_alt).expect(&key_error(key_alt));
-    let key_tab = EV_KEY::from_str(key_tab).expect(&key_error(key_tab));
-    let key_sft = EV_KEY::from_str(key_sft).expect(&key_error(key_sft));
+    let input = settings.input;
+    let (bindings_tx, bindings) = watch::channel(settings.bindings.clone());
 
-    let filenames: Vec<String> = if let Some(device) = device {
-        device.into_iter().map(Into::into).collect()
-    } else {
-        keyboard::try_find_keyboard().await?
+    let mut actions = match input {
+        Source::Keyboard => keyboard_actions(&settings, bindings).await?,
+        Source::Sway => binding::actions().await?,
     };
-    filenames.first().expect("keyboard not found");
 
-    let (tx, mut kb) = mpsc::channel(10);
-    for filename in filenames.iter().map(Path::new) {
-        let file = try_open_file(filename)
-            .await
-            .expect("device is not a keyboard or permission denied");
+    let exclude = settings.exclude.clone();
+    let pin = settings.pin.clone();
+    let state = state::path();
+    let mut swayalttab = SwayAlttab::new(settings.scope, exclude, pin, state)
+        .await
+        .unwrap();
+    swayalttab.live_preview = settings.live_preview;
+    swayalttab.set_highlight(settings.highlight).await?;
+    swayalttab.set_renderer(settings.renderer.clone());
+    let mut sway = Box::pin(SwayAlttab::sway_events().await.unwrap());
+    let mut requests = control::listen().await?;
+    let mut control = true;
+    let mut hangup = signal(SignalKind::hangup())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
 
-        let stream = keyboard::new_stream(file).await.unwrap();
-        let tx = tx.clone();
+    'main: loop {
+        let result = select! {
+            action = actions.next() => match action {
+                Some(Ok(input)) => swayalttab.process_input(input).await,
+                Some(Err(err)) if input == Source::Keyboard => return Err(err),
+                Some(Err(err)) => Err(err),
+                None if input == Source::Keyboard => {
+                    eprintln!("all keyboards are detached");
+                    break;
+                }
+                None => Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            },
+            ev = sway.next() => match ev {
+                Some(Ok(Event::Shutdown(_))) => break,
+                Some(Ok(ev)) => swayalttab.process_sway_event(ev).await,
+                Some(Err(err)) => Err(err),
+                None => Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            },
+            request = requests.recv(), if control => match request {
+                Some(request) => {
+                    let response = swayalttab.process_command(request.command).await;
+                    request.reply.send(response.map_err(|err| err.to_string())).ok();
+                    Ok(())
+                }
+                None => {
+                    eprintln!("control socket is disabled");
+                    control = false;
+                    Ok(())
+                }
+            },
+            _ = hangup.recv() => {
+                let path = config_path.as_deref();
+                reload(&args, path, &mut settings, &mut swayalttab, &bindings_tx).await
+            }
+            _ = terminate.recv() => break,
+            _ = interrupt.recv() => break,
+        };
 
-        tokio::spawn(async move {
-            pin_mut!(stream);
-            while let Some(ev) = stream.next().await {
-                tx.send(ev).await.unwrap();
+        if let Err(err) = result {
+            eprintln!("connection to sway is lost: {}", err);
+
+            let mut delay = RECONNECT_DELAY;
+            loop {
+                let reconnected = select! {
+                    result = async {
+                        time::sleep(delay).await;
+                        reconnect(&mut swayalttab, input).await
+                    } => result,
+                    _ = terminate.recv() => break 'main,
+                    _ = interrupt.recv() => break 'main,
+                };
+                match reconnected {
+                    Ok((events, binding_actions)) => {
+                        sway = events;
+                        if let Some(binding_actions) = binding_actions {
+                            actions = binding_actions;
+                        }
+                        break;
+                    }
+                    Err(err) => eprintln!("reconnection to sway failed: {}", err),
+                }
+                delay = cmp::min(delay * 2, RECONNECT_DELAY_MAX);
             }
-        });
+        }
+    }
+
+    if let Err(err) = swayalttab.highlight_window(None).await {
+        eprintln!("can't remove highlighting of window: {}", err);
+    }
+    swayalttab.try_save_state().await;
+    control::close().await;
+    Ok(())
+}
+
+/// Find running sway, subscribe to its events and take windows from it
+///
+/// Returns new stream of sway events and new stream of [`Action`] if
+/// `input` is sway bindings
+async fn reconnect(
+    swayalttab: &mut SwayAlttab,
+    input: Source,
+) -> Fallible<(Pin<Box<EventStream>>, Option<Actions>)> {
+    resolve_swaysock().await?;
+
+    let events = Box::pin(SwayAlttab::sway_events().await?);
+    let actions = match input {
+        Source::Keyboard => None,
+        Source::Sway => Some(binding::actions().await?),
+    };
+    swayalttab.reconnect().await?;
+
+    Ok((events, actions))
+}
+
+/// Find socket of running sway and remember it in `SWAYSOCK`
+///
+/// Sway creates new socket `$XDG_RUNTIME_DIR/sway-ipc.<uid>.<pid>.sock` at
+/// each start, so `SWAYSOCK` from environment can be outdated
+async fn resolve_swaysock() -> io::Result<()> {
+    if let Some(path) = env::var_os("SWAYSOCK") {
+        if UnixStream::connect(&path).await.is_ok() {
+            return Ok(());
+        }
     }
 
-    let swayalttab = SwayAlttab::new(key_tab, key_alt, key_sft).await.unwrap();
-    let sway = SwayAlttab::sway_events().await.unwrap();
+    let dir = env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new("/run/user").join(getuid().to_string()));
+    let prefix = format!("sway-ipc.{}.", getuid());
 
-    pin_mut!(sway);
-    pin_mut!(swayalttab);
+    let mut entries = fs::read_dir(dir).await?;
+    let mut sockets = vec![];
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with(&prefix) && name.ends_with(".sock") {
+            let modified = entry.metadata().await?.modified()?;
+            sockets.push((modified, entry.path()));
+        }
+    }
 
-    loop {
-        select! {
-            ev = kb.recv() => {
-                let ev = ev.expect("keyboard stream error")?;
-                swayalttab.process_keyboard_event(ev).await?;
+    sockets.sort();
+    for (_, path) in sockets.into_iter().rev() {
+        if UnixStream::connect(&path).await.is_ok() {
+            env::set_var("SWAYSOCK", path);
+            return Ok(());
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "socket of running sway is not found",
+    ))
+}
+
+/// Read configuration file at `path`
+///
+/// The file is required only if it's given in `args`
+async fn read_config(args: &ArgMatches<'_>, path: Option<&Path>) -> io::Result<Config> {
+    match path {
+        Some(path) => Config::read(path, args.is_present("config")).await,
+        None => Ok(Config::default()),
+    }
+}
+
+/// Read configuration file at `path` again and apply new settings
+///
+/// Changes of input and devices are applied only after restart
+async fn reload(
+    args: &ArgMatches<'_>,
+    path: Option<&Path>,
+    settings: &mut Settings,
+    swayalttab: &mut SwayAlttab,
+    bindings: &watch::Sender<Vec<Keys>>,
+) -> Result<(), Error> {
+    let config = read_config(args, path).await.map_err(|err| err.to_string());
+    let new = match config.and_then(|config| Settings::new(args, &config)) {
+        Ok(new) => new,
+        Err(err) => {
+            eprintln!("configuration is not reloaded: {}", err);
+            return Ok(());
+        }
+    };
+
+    if new.input != settings.input
+        || new.devices != settings.devices
+        || new.detection != settings.detection
+    {
+        eprintln!("changes of input and devices are applied after restart");
+    }
+
+    swayalttab.scope = new.scope;
+    swayalttab.live_preview = new.live_preview;
+    swayalttab.set_highlight(new.highlight).await?;
+    swayalttab.set_renderer(new.renderer.clone());
+    bindings.send(new.bindings.clone()).ok();
+    let (exclude, pin) = (new.exclude.clone(), new.pin.clone());
+    *settings = new;
+    swayalttab.set_rules(exclude, pin).await
+}
+
+/// Print all event devices and why they are accepted or rejected as keyboards
+async fn list_devices(detection: &Detection) -> io::Result<()> {
+    for candidate in detection.candidates().await? {
+        let verdict = match candidate.rejected {
+            Some(reason) => format!("rejected: {}", reason),
+            None => "accepted".to_string(),
+        };
+        println!(
+            "{}\t{}\t{}",
+            candidate.path.display(),
+            candidate.name,
+            verdict
+        );
+    }
+    Ok(())
+}
+
+/// Open keyboard devices from `settings` and create stream of [`Action`] from
+/// them with key sets from `bindings`
+async fn keyboard_actions(
+    settings: &Settings,
+    bindings: watch::Receiver<Vec<Keys>>,
+) -> Fallible<Actions> {
+    let (tx, kb) = mpsc::channel(10);
+    let readers = Readers::new(tx);
+
+    if !settings.devices.is_empty() {
+        for filename in &settings.devices {
+            readers
+                .open(filename)
+                .await
+                .expect("device is not a keyboard or permission denied");
+        }
+    } else {
+        let detection = settings.detection.clone();
+        for filename in keyboard::try_find_keyboard(&detection).await? {
+            if let Err(err) = readers.open(&filename).await {
+                eprintln!("{} is skipped: {}", filename.display(), err);
             }
-            ev = sway.next() => {
-                let ev = ev.expect("sway events stream error")?;
-                swayalttab.process_sway_event(ev).await?;
+        }
+
+        tokio::spawn(async move {
+            if let Err(err) = readers.watch(detection).await {
+                eprintln!("new keyboards are not watched: {}", err);
             }
-        };
+        });
     }
+
+    Ok(keyboard::actions(kb, bindings))
 }
 
 /// try open the file
@@ -257,3 +1451,59 @@ async fn try_open_file(filepath: &Path) -> io::Result<fs::File> {
 
     fs::File::open(filepath).await
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Scope, SwayAlttab};
+
+    /// Create [`SwayAlttab`] with windows 1, 2, 3 on workspaces 10, 11, 12
+    /// on outputs 20, 21, 22
+    fn swayalttab() -> SwayAlttab {
+        let mut s = SwayAlttab::empty(Scope::All, vec![], vec![], None);
+        for (index, name) in ["1", "2", "3"].iter().enumerate() {
+            let index = index as i64;
+            let output = format!("OUT-{}", name);
+            s.stack_holder.add(1 + index);
+            s.workspace_stack.add(10 + index);
+            s.workspace_names.insert(10 + index, name.to_string());
+            s.output_stack.add(20 + index);
+            s.output_names.insert(20 + index, output.clone());
+            s.outputs.insert(name.to_string(), output);
+        }
+        s
+    }
+
+    /// Select next window and emulate events of its focusing
+    fn preview_next(s: &mut SwayAlttab, workspace: i64) {
+        let id = s.stack_holder.preview_next().unwrap();
+        s.ignore_move_up = Some(id);
+        s.workspace_focused(workspace);
+        s.window_focused(id);
+    }
+
+    #[test]
+    fn workspaces_while_preview() {
+        let mut s = swayalttab();
+        let workspaces = |s: &SwayAlttab| s.workspace_stack.iter().collect::<Vec<_>>();
+        let outputs = |s: &SwayAlttab| s.output_stack.iter().collect::<Vec<_>>();
+
+        preview_next(&mut s, 11);
+        preview_next(&mut s, 12);
+        let initial = s.cancel_window_preview().unwrap();
+        s.ignore_move_up = Some(initial);
+        s.workspace_focused(10);
+        s.window_focused(initial);
+        assert_eq!(workspaces(&s), vec![10, 11, 12]);
+        assert_eq!(outputs(&s), vec![20, 21, 22]);
+
+        preview_next(&mut s, 11);
+        preview_next(&mut s, 12);
+        assert_eq!(workspaces(&s), vec![10, 11, 12]);
+        assert_eq!(s.finish_window_preview(), Some(3));
+        assert_eq!(workspaces(&s), vec![12, 10, 11]);
+        assert_eq!(outputs(&s), vec![22, 20, 21]);
+
+        s.workspace_focused(11);
+        assert_eq!(workspaces(&s), vec![11, 12, 10]);
+    }
+}
@@ -0,0 +1,167 @@
+use {
+    serde::Serialize,
+    std::{process::Stdio, time::Duration},
+    tokio::{
+        io::{self, AsyncWriteExt as _},
+        process::{Child, ChildStdin, Command},
+        select,
+        sync::watch,
+        time,
+    },
+};
+
+/// Time of writing message after which helper process is killed
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// `Message` is line of JSON for renderer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Message<'a> {
+    /// show windows in order of switching with selected one
+    Show {
+        windows: Vec<Item<'a>>,
+        /// index of selected window in `windows`
+        selected: usize,
+    },
+    /// hide windows after commit or cancel
+    Hide,
+}
+
+/// `Item` is window in list of [`Message::Show`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Item<'a> {
+    pub id: i64,
+    pub title: Option<&'a str>,
+    pub app_id: Option<&'a str>,
+}
+
+/// `Renderer` is long-running helper process which draws list of windows
+///
+/// It receives [`Message`] as JSON lines on stdin and it's restarted if it's
+/// exited. Messages are written by separate task, so helper which doesn't
+/// read them can't block the daemon, only the newest of unwritten messages is
+/// kept
+#[derive(Debug)]
+pub struct Renderer {
+    /// shell command of helper process
+    command: String,
+    /// the newest message for writer task of running helper process
+    tx: Option<watch::Sender<Vec<u8>>>,
+}
+
+impl Renderer {
+    /// Create [`Renderer`] with shell `command`, the process is started on
+    /// demand
+    pub fn new(command: String) -> Self {
+        Self { command, tx: None }
+    }
+
+    /// Get shell command of helper process
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// Start helper process if it isn't started
+    pub fn start(&mut self) -> io::Result<()> {
+        if self.tx.is_some() {
+            return Ok(());
+        }
+
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin of renderer is piped");
+        let (tx, rx) = watch::channel(Vec::new());
+        tokio::spawn(write(child, stdin, rx));
+        self.tx = Some(tx);
+        Ok(())
+    }
+
+    /// Pass `message` to helper process, it replaces unwritten message
+    ///
+    /// Exited process is restarted, but not for hiding
+    pub fn send(&mut self, message: &Message<'_>) -> io::Result<()> {
+        let mut line = serde_json::to_vec(message)?;
+        line.push(b'\n');
+
+        for _ in 0..2 {
+            if self.tx.is_none() && *message == Message::Hide {
+                return Ok(());
+            }
+            self.start()?;
+            match self.tx.as_ref().map(|tx| tx.send(line.clone())) {
+                Some(Err(_)) => self.tx = None,
+                _ => return Ok(()),
+            }
+        }
+        Err(io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+}
+
+/// Write the newest line from `rx` to `stdin` of helper process `child` till
+/// its exit
+///
+/// The process is killed after failed or too long writing
+async fn write(mut child: Child, mut stdin: ChildStdin, mut rx: watch::Receiver<Vec<u8>>) {
+    loop {
+        let changed = select! {
+            changed = rx.changed() => changed.is_ok(),
+            _ = child.wait() => false,
+        };
+        if !changed {
+            break;
+        }
+        let line = rx.borrow().clone();
+        match time::timeout(WRITE_TIMEOUT, stdin.write_all(&line)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => {
+                eprintln!("renderer failed: {}", err);
+                break;
+            }
+            Err(_) => {
+                eprintln!("renderer doesn't read messages, it's killed");
+                break;
+            }
+        }
+    }
+    drop(child);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Item, Message};
+
+    #[test]
+    fn serialize() {
+        let show = Message::Show {
+            windows: vec![
+                Item {
+                    id: 1,
+                    title: Some("vim"),
+                    app_id: Some("foot"),
+                },
+                Item {
+                    id: 2,
+                    title: None,
+                    app_id: None,
+                },
+            ],
+            selected: 1,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&show).unwrap(),
+            concat!(
+                r#"{"event":"show","windows":[{"id":1,"title":"vim","app_id":"foot"},"#,
+                r#"{"id":2,"title":null,"app_id":null}],"selected":1}"#
+            )
+        );
+        assert_eq!(
+            serde_json::to_string(&Message::Hide).unwrap(),
+            r#"{"event":"hide"}"#
+        );
+    }
+}
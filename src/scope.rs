@@ -0,0 +1,30 @@
+use std::str::FromStr;
+
+/// `Scope` is set of windows for switching between
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// all windows
+    All,
+    /// windows of focused workspace
+    Workspace,
+    /// windows of all workspaces on focused output
+    Output,
+}
+
+impl Scope {
+    /// Names of all scopes
+    pub const NAMES: &'static [&'static str] = &["all", "workspace", "output"];
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Self::All),
+            "workspace" => Ok(Self::Workspace),
+            "output" => Ok(Self::Output),
+            _ => Err(format!("incorrect scope {}", s)),
+        }
+    }
+}
@@ -1,111 +1,180 @@
-/// `Node` is internal type for storing data (of [`i64`]) in [`Stack`]
-#[derive(Debug, Clone)]
-struct Node {
-    value: i64,
-    next: Option<Box<Node>>,
+use std::collections::HashMap;
+
+/// `Link` is internal type for storing neighbours of value in [`Stack`]
+#[derive(Debug, Clone, Copy)]
+struct Link {
+    prev: Option<i64>,
+    next: Option<i64>,
 }
 
 /// `Stack` is type for storing data (of [`i64`]) in stack
+///
+/// Values are linked with each other through the hash map, so moving value to
+/// the up and removing it don't depend on the depth of stack
 #[derive(Debug, Clone)]
 pub struct Stack {
-    head: Option<Box<Node>>,
+    links: HashMap<i64, Link>,
+    head: Option<i64>,
+    tail: Option<i64>,
 }
 
-impl Node {
-    /// Create new [`Node`] with `value`
-    pub fn new(value: i64) -> Self {
-        Self { value, next: None }
-    }
-
-    /// Remove `value` from stack
-    pub fn remove(self, value: i64) -> Option<Box<Self>> {
-        if self.value == value {
-            self.next
-        } else {
-            Some(Box::new(Self {
-                value: self.value,
-                next: self.next.and_then(|next| next.remove(value)),
-            }))
-        }
-    }
-
-    /// Move `value` to the head
-    pub fn move_up(self, value: i64) -> Box<Self> {
-        Box::new(Self {
-            value,
-            next: self.remove(value),
-        })
-    }
-
-    /// Add `value` to the tail
-    pub fn add(mut self, value: i64) -> Box<Self> {
-        if self.value != value {
-            self.next = self
-                .next
-                .map(|next| next.add(value))
-                .or_else(|| Some(Box::new(Node::new(value))));
-        }
-        Box::new(self)
-    }
-
-    /// Try get value in `depth` of stack
-    pub fn get(&self, depth: usize) -> Option<i64> {
-        if depth == 0 {
-            Some(self.value)
-        } else {
-            self.next.as_ref().and_then(|next| next.get(depth - 1))
-        }
-    }
-
-    /// Depth of stack
-    pub fn depth(&self) -> usize {
-        self.next.as_ref().map(|next| next.depth()).unwrap_or(0) + 1
-    }
+/// `Iter` is iterator over windows of [`Stack`]
+#[derive(Clone)]
+pub struct Iter<'a> {
+    stack: &'a Stack,
+    front: Option<i64>,
+    back: Option<i64>,
+    len: usize,
 }
 
 impl Stack {
     /// Create new empty [`Stack`]
     pub fn new() -> Self {
-        Self { head: None }
+        Self {
+            links: HashMap::new(),
+            head: None,
+            tail: None,
+        }
     }
 
     /// Move window with `id` to the up of stack
     pub fn move_up(&mut self, id: i64) -> i64 {
-        self.head = self
-            .head
-            .take()
-            .map(|head| head.move_up(id))
-            .or_else(|| Some(Box::new(Node::new(id))));
+        if self.head != Some(id) {
+            self.unlink(id);
+            self.push_head(id);
+        }
         id
     }
 
     /// Add window with `id` to the down of stack
     pub fn add(&mut self, id: i64) -> i64 {
-        self.head = self
-            .head
-            .take()
-            .map(|head| head.add(id))
-            .or_else(|| Some(Box::new(Node::new(id))));
+        if !self.links.contains_key(&id) {
+            self.push_tail(id);
+        }
         id
     }
 
     /// Remove window with `id` from the stack
     pub fn remove(&mut self, id: i64) -> i64 {
-        self.head = self.head.take().and_then(|head| head.remove(id));
+        self.unlink(id);
         id
     }
 
     /// Get window `id` in `depth` of stack
+    ///
+    /// Walks from the nearest end of stack
     pub fn get(&self, depth: usize) -> Option<i64> {
-        self.head.as_ref().and_then(|head| head.get(depth))
+        let size = self.depth();
+        if depth >= size {
+            None
+        } else if depth <= size / 2 {
+            self.walk(self.head, depth, |link| link.next)
+        } else {
+            self.walk(self.tail, size - 1 - depth, |link| link.prev)
+        }
     }
 
     /// Get depth of stack
     pub fn depth(&self) -> usize {
-        self.head.as_ref().map(|head| head.depth()).unwrap_or(0)
+        self.links.len()
+    }
+
+    /// Check if window with `id` is in stack
+    pub fn contains(&self, id: i64) -> bool {
+        self.links.contains_key(&id)
+    }
+
+    /// Iterate over windows from the up to the down of stack
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            stack: self,
+            front: self.head,
+            back: self.tail,
+            len: self.depth(),
+        }
+    }
+
+    /// Make `steps` steps from `start` in direction chosen by `step`
+    fn walk(
+        &self,
+        start: Option<i64>,
+        steps: usize,
+        step: fn(&Link) -> Option<i64>,
+    ) -> Option<i64> {
+        (0..steps).try_fold(start?, |id, _| step(&self.links[&id]))
+    }
+
+    /// Put not linked `id` to the head
+    fn push_head(&mut self, id: i64) {
+        let next = self.head.replace(id);
+        match next {
+            Some(next) => self.link_mut(next).prev = Some(id),
+            None => self.tail = Some(id),
+        }
+        self.links.insert(id, Link { prev: None, next });
+    }
+
+    /// Put not linked `id` to the tail
+    fn push_tail(&mut self, id: i64) {
+        let prev = self.tail.replace(id);
+        match prev {
+            Some(prev) => self.link_mut(prev).next = Some(id),
+            None => self.head = Some(id),
+        }
+        self.links.insert(id, Link { prev, next: None });
+    }
+
+    /// Unlink `id` from its neighbours
+    fn unlink(&mut self, id: i64) {
+        if let Some(Link { prev, next }) = self.links.remove(&id) {
+            match prev {
+                Some(prev) => self.link_mut(prev).next = next,
+                None => self.head = next,
+            }
+            match next {
+                Some(next) => self.link_mut(next).prev = prev,
+                None => self.tail = prev,
+            }
+        }
+    }
+
+    fn link_mut(&mut self, id: i64) -> &mut Link {
+        self.links.get_mut(&id).expect("broken link in stack")
     }
 }
 
+impl Iterator for Iter<'_> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        if self.len == 0 {
+            return None;
+        }
+        let id = self.front?;
+        self.front = self.stack.links[&id].next;
+        self.len -= 1;
+        Some(id)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<i64> {
+        if self.len == 0 {
+            return None;
+        }
+        let id = self.back?;
+        self.back = self.stack.links[&id].prev;
+        self.len -= 1;
+        Some(id)
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
 #[cfg(test)]
 mod test {
     use super::Stack;
@@ -258,4 +327,54 @@ mod test {
         assert_eq!(s.get(3), Some(3));
         assert_eq!(s.get(4), None);
     }
+
+    #[test]
+    fn get_from_tail() {
+        let mut s = Stack::new();
+
+        (1..=5).for_each(|id| {
+            s.add(id);
+        });
+
+        s.move_up(4);
+        s.remove(2);
+
+        assert_eq!(s.depth(), 4);
+        assert_eq!(s.get(3), Some(5));
+        assert_eq!(s.get(2), Some(3));
+        assert_eq!(s.get(1), Some(1));
+        assert_eq!(s.get(0), Some(4));
+    }
+
+    #[test]
+    fn iter() {
+        let mut s = Stack::new();
+
+        s.add(1);
+        s.add(2);
+        s.add(3);
+
+        s.move_up(2);
+
+        assert_eq!(s.iter().collect::<Vec<_>>(), vec![2, 1, 3]);
+        assert_eq!(s.iter().rev().collect::<Vec<_>>(), vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn deep() {
+        let mut s = Stack::new();
+
+        (0..100_000).for_each(|id| {
+            s.add(id);
+        });
+
+        s.move_up(99_999);
+        s.remove(0);
+
+        assert_eq!(s.depth(), 99_999);
+        assert_eq!(s.get(0), Some(99_999));
+        assert_eq!(s.get(1), Some(1));
+        assert_eq!(s.get(99_998), Some(99_998));
+        assert_eq!(s.get(99_999), None);
+    }
 }
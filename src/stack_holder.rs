@@ -1,4 +1,20 @@
-use crate::stack::Stack;
+use {
+    crate::{
+        stack::{Iter, Stack},
+        window_info::WindowInfo,
+    },
+    std::{
+        cmp,
+        collections::{btree_map, BTreeMap, HashMap, HashSet},
+        iter::Peekable,
+    },
+};
+
+/// Slot of the first pinned window by default
+///
+/// The slot 1 is kept for the previous window, so one switch still returns to
+/// it
+pub const FIRST_PIN_SLOT: usize = 2;
 
 /// Hold stack of windows
 ///
@@ -6,7 +22,16 @@ use crate::stack::Stack;
 #[derive(Debug, Clone)]
 pub struct StackHolder {
     window_stack: Stack,
+    /// position of previewed window in order of switching
     preview_depth: usize,
+    /// pinned windows by their slots (positions in order of switching)
+    pinned: BTreeMap<usize, i64>,
+    /// slots of pinned windows
+    slots: HashMap<i64, usize>,
+    /// windows pinned by criteria, other windows are pinned by user
+    pinned_by_rule: HashSet<i64>,
+    /// properties of each window in the stack
+    info: HashMap<i64, WindowInfo>,
 }
 
 impl StackHolder {
@@ -15,22 +40,124 @@ impl StackHolder {
         Self {
             window_stack: Stack::new(),
             preview_depth: 0,
+            pinned: BTreeMap::new(),
+            slots: HashMap::new(),
+            pinned_by_rule: HashSet::new(),
+            info: HashMap::new(),
         }
     }
 
     /// Move window with `id` to up of stack
     pub fn move_up(&mut self, id: i64) {
         self.window_stack.move_up(id);
+        self.info.entry(id).or_default();
     }
 
     /// Add new window with `id`
     pub fn add(&mut self, id: i64) {
         self.window_stack.add(id);
+        self.info.entry(id).or_default();
     }
 
     /// Remove window with `id`
     pub fn remove(&mut self, id: i64) {
         self.window_stack.remove(id);
+        self.info.remove(&id);
+        self.unpin(id);
+    }
+
+    /// Get properties of window with `id`
+    pub fn info(&self, id: i64) -> Option<&WindowInfo> {
+        self.info.get(&id)
+    }
+
+    /// Get mutable properties of window with `id`
+    pub fn info_mut(&mut self, id: i64) -> Option<&mut WindowInfo> {
+        self.info.get_mut(&id)
+    }
+
+    /// Replace properties of window with `id` if it's in the stack
+    ///
+    /// Time of the last focusing is kept
+    pub fn set_info(&mut self, id: i64, info: WindowInfo) {
+        if let Some(old) = self.info.get_mut(&id) {
+            let focused_at = old.focused_at;
+            *old = WindowInfo { focused_at, ..info };
+        }
+    }
+
+    /// Iterate over windows with their properties from the up to the down of
+    /// stack
+    pub fn windows(&self) -> impl Iterator<Item = (i64, &WindowInfo)> + '_ {
+        self.iter()
+            .filter_map(move |id| self.info.get(&id).map(|info| (id, info)))
+    }
+
+    /// Pin window with `id` at `slot` or after the last pinned window
+    ///
+    /// Window at the slot is unpinned. Returns slot of the window
+    pub fn pin(&mut self, id: i64, slot: Option<usize>) -> usize {
+        self.unpin(id);
+        let slot = slot.unwrap_or_else(|| {
+            let last = self.pinned.keys().next_back().map(|slot| slot + 1);
+            cmp::max(last.unwrap_or(0), FIRST_PIN_SLOT)
+        });
+        if let Some(old) = self.pinned.insert(slot, id) {
+            self.slots.remove(&old);
+            self.pinned_by_rule.remove(&old);
+        }
+        self.slots.insert(id, slot);
+        slot
+    }
+
+    /// Pin window with `id` at `slot` of matched criteria if the slot is free,
+    /// unpin the window if it's pinned by criteria and `slot` is [`None`]
+    ///
+    /// Windows pinned by user aren't changed
+    pub fn pin_by_rule(&mut self, id: i64, slot: Option<usize>) {
+        match (slot, self.slot(id)) {
+            (Some(slot), None) if self.pinned_at(slot).is_none() => {
+                self.pin(id, Some(slot));
+                self.pinned_by_rule.insert(id);
+            }
+            (None, Some(_)) if self.pinned_by_rule.contains(&id) => {
+                self.unpin(id);
+            }
+            _ => {}
+        }
+    }
+
+    /// Unpin window with `id`
+    ///
+    /// Returns `false` if the window isn't pinned
+    pub fn unpin(&mut self, id: i64) -> bool {
+        self.pinned_by_rule.remove(&id);
+        match self.slots.remove(&id) {
+            Some(slot) => self.pinned.remove(&slot).is_some(),
+            None => false,
+        }
+    }
+
+    /// Get slot of pinned window with `id`
+    pub fn slot(&self, id: i64) -> Option<usize> {
+        self.slots.get(&id).copied()
+    }
+
+    /// Get window pinned at `slot`
+    pub fn pinned_at(&self, slot: usize) -> Option<i64> {
+        self.pinned.get(&slot).copied()
+    }
+
+    /// Iterate in order of switching: the top window, then other windows from
+    /// the stack with pinned windows at their slots
+    pub fn order(&self) -> Order<'_> {
+        Order {
+            holder: self,
+            top: self.window_stack.get(0),
+            windows: self.window_stack.iter(),
+            pinned: self.pinned.iter().peekable(),
+            index: 0,
+        }
     }
 
     pub fn get(&self, depth: usize) -> Option<i64> {
@@ -41,43 +168,143 @@ impl StackHolder {
         self.window_stack.depth()
     }
 
+    /// Remove windows with `id` not accepted by `filter`
+    pub fn retain<F: Fn(i64) -> bool>(&mut self, filter: F) {
+        let removed: Vec<_> = self.iter().filter(|&id| !filter(id)).collect();
+        removed.into_iter().for_each(|id| self.remove(id));
+    }
+
+    /// Iterate over windows from the up to the down of stack
+    pub fn iter(&self) -> Iter<'_> {
+        self.window_stack.iter()
+    }
+
     /// Finish preview and move currently focused window to the up
     pub fn preview_finish(&mut self) {
-        if let Some(id) = self.window_stack.get(self.preview_depth) {
-            self.window_stack.move_up(id);
+        if self.preview_depth != 0 {
+            if let Some(id) = self.order().nth(self.preview_depth) {
+                self.window_stack.move_up(id);
+            }
         }
         self.preview_depth = 0;
     }
 
+    /// Cancel preview w/o moving windows in stack
+    ///
+    /// Returns initial window for focusing if other window was selected
+    pub fn preview_cancel(&mut self) -> Option<i64> {
+        let depth = self.preview_depth;
+        self.preview_depth = 0;
+        if depth != 0 {
+            self.window_stack.get(0)
+        } else {
+            None
+        }
+    }
+
     /// Select and focus next window (w/o moving windows in stack)
     pub fn preview_next(&mut self) -> Option<i64> {
-        self.preview_depth += 1;
-        self.window_stack.get(self.preview_depth).or_else(|| {
-            self.preview_depth = 0;
-            self.window_stack.get(0)
-        })
+        self.preview_next_by(|_| true)
     }
 
     /// Select and focus to previously window (w/o moving windows in stack)
     pub fn preview_prev(&mut self) -> Option<i64> {
-        self.preview_depth
-            .checked_sub(1)
-            .and_then(|depth| {
-                self.preview_depth = depth;
-                self.window_stack.get(depth)
-            })
-            .or_else(|| {
-                self.window_stack.depth().checked_sub(1).and_then(|depth| {
-                    self.preview_depth = depth;
-                    self.window_stack.get(depth)
-                })
-            })
+        self.preview_prev_by(|_| true)
+    }
+
+    /// Select and focus next window accepted by `filter` (w/o moving windows
+    /// in stack)
+    pub fn preview_next_by<F: Fn(i64) -> bool>(&mut self, filter: F) -> Option<i64> {
+        let depth = self.preview_depth;
+        let windows = self.order().enumerate();
+        let found = windows
+            .clone()
+            .skip(depth + 1)
+            .chain(windows.take(depth + 1))
+            .find(|&(_, id)| filter(id));
+        self.preview_select(found)
+    }
+
+    /// Select and focus to previously window accepted by `filter` (w/o moving
+    /// windows in stack)
+    pub fn preview_prev_by<F: Fn(i64) -> bool>(&mut self, filter: F) -> Option<i64> {
+        let depth = self.preview_depth;
+        let windows = self.order().enumerate();
+        let found = windows
+            .clone()
+            .take(depth)
+            .filter(|&(_, id)| filter(id))
+            .last()
+            .or_else(|| windows.skip(depth).filter(|&(_, id)| filter(id)).last());
+        self.preview_select(found)
+    }
+
+    /// Get window selected by preview which isn't finished
+    pub fn preview(&self) -> Option<i64> {
+        if self.preview_depth != 0 {
+            self.order().nth(self.preview_depth)
+        } else {
+            None
+        }
+    }
+
+    /// Remember depth of `found` window as previewed
+    fn preview_select(&mut self, found: Option<(usize, i64)>) -> Option<i64> {
+        self.preview_depth = found.map(|(depth, _)| depth).unwrap_or(0);
+        found.map(|(_, id)| id)
+    }
+}
+
+/// `Order` is iterator over windows in order of switching
+///
+/// Windows of the stack are merged with pinned windows by slots, so only
+/// passed windows are visited
+#[derive(Clone)]
+pub struct Order<'a> {
+    holder: &'a StackHolder,
+    top: Option<i64>,
+    windows: Iter<'a>,
+    pinned: Peekable<btree_map::Iter<'a, usize, i64>>,
+    /// position of the next window
+    index: usize,
+}
+
+impl Iterator for Order<'_> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        let (holder, top) = (self.holder, self.top);
+        // the top window stays on the top even if it's pinned
+        while let Some(&(_, &id)) = self.pinned.peek() {
+            if Some(id) != top && holder.window_stack.contains(id) {
+                break;
+            }
+            self.pinned.next();
+        }
+
+        let id = match self.pinned.peek() {
+            Some(&(&slot, &id)) if slot <= self.index => {
+                self.pinned.next();
+                id
+            }
+            _ => {
+                let window = self
+                    .windows
+                    .find(|&id| Some(id) == top || holder.slot(id).is_none());
+                match window {
+                    Some(id) => id,
+                    None => *self.pinned.next()?.1,
+                }
+            }
+        };
+        self.index += 1;
+        Some(id)
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::StackHolder;
+    use {super::StackHolder, crate::window_info::WindowInfo, std::time::SystemTime};
 
     #[test]
     fn preview_next() {
@@ -103,7 +330,7 @@ mod test {
         assert_eq!(s.preview_next(), None);
         assert_eq!(s.preview_next(), None);
     }
-    
+
     #[test]
     fn preview_next_1() {
         let mut s = StackHolder::new();
@@ -140,7 +367,7 @@ mod test {
         assert_eq!(s.preview_prev(), None);
         assert_eq!(s.preview_prev(), None);
     }
-    
+
     #[test]
     fn preview_prev_1() {
         let mut s = StackHolder::new();
@@ -152,6 +379,164 @@ mod test {
         assert_eq!(s.preview_prev(), Some(1));
     }
 
+    #[test]
+    fn preview() {
+        let mut s = StackHolder::new();
+
+        s.add(1);
+        s.add(2);
+        s.add(3);
+
+        assert_eq!(s.preview(), None);
+        s.preview_next();
+        s.preview_next();
+        assert_eq!(s.preview(), Some(3));
+        s.preview_cancel();
+        assert_eq!(s.preview(), None);
+    }
+
+    #[test]
+    fn preview_next_by() {
+        let mut s = StackHolder::new();
+
+        s.add(1);
+        s.add(2);
+        s.add(3);
+        s.add(4);
+
+        let odd = |id| id % 2 == 1;
+
+        assert_eq!(s.preview_next_by(odd), Some(3));
+        assert_eq!(s.preview_next_by(odd), Some(1));
+        assert_eq!(s.preview_next_by(odd), Some(3));
+        assert_eq!(s.preview_next_by(|_| false), None);
+        assert_eq!(s.preview_next_by(odd), Some(3));
+    }
+
+    #[test]
+    fn preview_prev_by() {
+        let mut s = StackHolder::new();
+
+        s.add(1);
+        s.add(2);
+        s.add(3);
+        s.add(4);
+
+        let even = |id| id % 2 == 0;
+
+        assert_eq!(s.preview_prev_by(even), Some(4));
+        assert_eq!(s.preview_prev_by(even), Some(2));
+        assert_eq!(s.preview_prev_by(even), Some(4));
+    }
+
+    #[test]
+    fn preview_finish_by() {
+        let mut s = StackHolder::new();
+
+        s.add(1);
+        s.add(2);
+        s.add(3);
+        s.add(4);
+
+        s.preview_next_by(|id| id > 2);
+        s.preview_next_by(|id| id > 2);
+        s.preview_finish();
+
+        assert_eq!(s.get(0), Some(4));
+        assert_eq!(s.get(1), Some(1));
+        assert_eq!(s.get(2), Some(2));
+        assert_eq!(s.get(3), Some(3));
+    }
+
+    #[test]
+    fn retain() {
+        let mut s = StackHolder::new();
+
+        s.add(1);
+        s.add(2);
+        s.add(3);
+        s.add(4);
+
+        s.move_up(3);
+        s.retain(|id| id != 1 && id != 4);
+
+        assert_eq!(s.get(0), Some(3));
+        assert_eq!(s.get(1), Some(2));
+        assert_eq!(s.get(2), None);
+    }
+
+    #[test]
+    fn preview_cancel() {
+        let mut s = StackHolder::new();
+
+        s.add(1);
+        s.add(2);
+        s.add(3);
+
+        s.preview_next();
+        s.preview_next();
+
+        assert_eq!(s.preview_cancel(), Some(1));
+
+        assert_eq!(s.get(0), Some(1));
+        assert_eq!(s.get(1), Some(2));
+        assert_eq!(s.get(2), Some(3));
+        assert_eq!(s.get(3), None);
+
+        s.preview_finish();
+
+        assert_eq!(s.get(0), Some(1));
+        assert_eq!(s.get(1), Some(2));
+        assert_eq!(s.get(2), Some(3));
+        assert_eq!(s.get(3), None);
+    }
+
+    #[test]
+    fn preview_cancel_0() {
+        let mut s = StackHolder::new();
+
+        assert_eq!(s.preview_cancel(), None);
+
+        s.add(1);
+        s.add(2);
+
+        assert_eq!(s.preview_cancel(), None);
+    }
+
+    #[test]
+    fn preview_cancel_wrapped() {
+        let mut s = StackHolder::new();
+
+        s.add(1);
+        s.add(2);
+
+        s.preview_next();
+        s.preview_next();
+
+        assert_eq!(s.preview_cancel(), None);
+    }
+
+    #[test]
+    fn preview_after_cancel() {
+        let mut s = StackHolder::new();
+
+        s.add(1);
+        s.add(2);
+        s.add(3);
+
+        s.preview_prev();
+        s.preview_cancel();
+
+        assert_eq!(s.preview_next(), Some(2));
+
+        s.preview_finish();
+
+        assert_eq!(s.get(0), Some(2));
+        assert_eq!(s.get(1), Some(1));
+        assert_eq!(s.get(2), Some(3));
+        assert_eq!(s.get(3), None);
+    }
+
     #[test]
     fn preview_finish_0() {
         let mut s = StackHolder::new();
@@ -255,4 +640,142 @@ mod test {
         assert_eq!(s.get(2), Some(3));
         assert_eq!(s.get(3), None);
     }
+
+    #[test]
+    fn pin() {
+        let mut s = StackHolder::new();
+
+        (1..=5).for_each(|id| s.add(id));
+
+        assert_eq!(s.pin(5, None), 2);
+        assert_eq!(s.order().collect::<Vec<_>>(), vec![1, 2, 5, 3, 4]);
+        assert_eq!(s.pin(4, None), 3);
+        assert_eq!(s.order().collect::<Vec<_>>(), vec![1, 2, 5, 4, 3]);
+        assert_eq!(s.pin(3, Some(10)), 10);
+        assert_eq!(s.order().collect::<Vec<_>>(), vec![1, 2, 5, 4, 3]);
+        assert_eq!(s.pinned_at(3), Some(4));
+        assert_eq!(s.slot(4), Some(3));
+        assert_eq!(s.slot(1), None);
+
+        assert_eq!(s.pin(2, Some(3)), 3);
+        assert_eq!(s.pinned_at(3), Some(2));
+        assert!(!s.unpin(4));
+        assert!(s.unpin(2));
+        assert!(!s.unpin(2));
+        assert_eq!(s.order().collect::<Vec<_>>(), vec![1, 2, 5, 4, 3]);
+    }
+
+    #[test]
+    fn pin_top() {
+        let mut s = StackHolder::new();
+
+        (1..=4).for_each(|id| s.add(id));
+        s.pin(1, Some(2));
+        s.pin(3, Some(3));
+
+        assert_eq!(s.order().collect::<Vec<_>>(), vec![1, 2, 4, 3]);
+        s.remove(3);
+        assert_eq!(s.pinned_at(3), None);
+        assert_eq!(s.order().collect::<Vec<_>>(), vec![1, 2, 4]);
+
+        s.pin(5, Some(3));
+        assert_eq!(s.order().collect::<Vec<_>>(), vec![1, 2, 4]);
+        s.add(5);
+        assert_eq!(s.order().collect::<Vec<_>>(), vec![1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn pin_by_rule() {
+        let mut s = StackHolder::new();
+
+        (1..=5).for_each(|id| s.add(id));
+
+        assert_eq!(s.pin(4, None), 2);
+        s.pin_by_rule(4, None);
+        assert_eq!(s.slot(4), Some(2));
+        s.pin_by_rule(5, Some(2));
+        assert_eq!(s.slot(5), None);
+        s.pin_by_rule(5, Some(3));
+        assert_eq!(s.slot(5), Some(3));
+        assert_eq!(s.order().collect::<Vec<_>>(), vec![1, 2, 4, 5, 3]);
+
+        s.pin(5, Some(3));
+        s.pin_by_rule(5, None);
+        assert_eq!(s.slot(5), Some(3));
+
+        s.pin_by_rule(3, Some(4));
+        s.pin_by_rule(3, None);
+        assert_eq!(s.slot(3), None);
+        assert_eq!(s.slot(4), Some(2));
+    }
+
+    #[test]
+    fn preview_finish_pinned() {
+        let mut s = StackHolder::new();
+
+        (1..=4).for_each(|id| s.add(id));
+        s.pin(4, None);
+
+        assert_eq!(s.preview_next(), Some(2));
+        assert_eq!(s.preview_next(), Some(4));
+        s.preview_finish();
+        assert_eq!(s.get(0), Some(4));
+        assert_eq!(s.order().collect::<Vec<_>>(), vec![4, 1, 2, 3]);
+
+        assert_eq!(s.preview_next(), Some(1));
+        s.preview_finish();
+        assert_eq!(s.get(1), Some(4));
+        assert_eq!(s.order().collect::<Vec<_>>(), vec![1, 2, 4, 3]);
+
+        assert_eq!(s.preview_next(), Some(2));
+        assert_eq!(s.preview_next(), Some(4));
+        assert_eq!(s.preview_next(), Some(3));
+        s.preview_finish();
+        assert_eq!(s.get(0), Some(3));
+        assert_eq!(s.order().collect::<Vec<_>>(), vec![3, 1, 4, 2]);
+    }
+
+    #[test]
+    fn preview_next_by_pinned() {
+        let mut s = StackHolder::new();
+
+        (1..=4).for_each(|id| s.add(id));
+        s.pin(4, None);
+
+        assert_eq!(s.preview_next_by(|id| id % 2 == 0), Some(2));
+        assert_eq!(s.preview_next_by(|id| id % 2 == 0), Some(4));
+        assert_eq!(s.preview_prev_by(|id| id != 2), Some(1));
+        s.preview_finish();
+        assert_eq!(s.order().collect::<Vec<_>>(), vec![1, 2, 4, 3]);
+    }
+
+    #[test]
+    fn info() {
+        let mut s = StackHolder::new();
+
+        s.add(1);
+        s.add(2);
+        s.info_mut(1).unwrap().focused_at = Some(SystemTime::UNIX_EPOCH);
+        s.set_info(
+            1,
+            WindowInfo {
+                title: Some("vim".to_string()),
+                ..WindowInfo::default()
+            },
+        );
+        s.set_info(3, WindowInfo::default());
+
+        let info = s.info(1).unwrap();
+        assert_eq!(info.title.as_deref(), Some("vim"));
+        assert_eq!(info.focused_at, Some(SystemTime::UNIX_EPOCH));
+        assert_eq!(s.info(2), Some(&WindowInfo::default()));
+        assert_eq!(s.info(3), None);
+
+        s.move_up(2);
+        let ids: Vec<_> = s.windows().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![2, 1]);
+
+        s.remove(1);
+        assert_eq!(s.info(1), None);
+    }
 }
@@ -0,0 +1,148 @@
+use {
+    serde::{Deserialize, Serialize},
+    std::{
+        env,
+        path::{Path, PathBuf},
+    },
+    tokio::{fs, io},
+};
+
+/// `Entry` is saved window of stack
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: i64,
+    pub pid: Option<i32>,
+    pub app_id: Option<String>,
+    pub title: Option<String>,
+}
+
+impl Entry {
+    /// Check if `other` is same window after restart of sway
+    fn same_window(&self, other: &Entry) -> bool {
+        self.pid == other.pid && self.app_id == other.app_id && self.title == other.title
+    }
+}
+
+/// Get path of file with saved stack
+/// `$XDG_STATE_HOME/sway-alttab/stack.json`
+pub fn path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/state")))?;
+    Some(dir.join(env!("CARGO_PKG_NAME")).join("stack.json"))
+}
+
+/// Save `entries` of stack to file at `path`
+///
+/// The file is replaced atomically
+pub async fn save(path: &Path, entries: &[Entry]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+
+    let content = serde_json::to_vec(entries)?;
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, content).await?;
+    fs::rename(&tmp, path).await
+}
+
+/// Load entries of stack from file at `path`
+///
+/// Missing file is empty stack
+pub async fn load(path: &Path) -> io::Result<Vec<Entry>> {
+    match fs::read(path).await {
+        Ok(content) => Ok(serde_json::from_slice(&content)?),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(err),
+    }
+}
+
+/// Match `saved` entries with `live` windows
+///
+/// Returns ids of live windows in saved order. Window is matched by id if its
+/// pid and application are same, otherwise by pid, application and title
+pub fn reconcile(saved: &[Entry], live: &[Entry]) -> Vec<i64> {
+    let mut matched = vec![false; live.len()];
+
+    saved
+        .iter()
+        .filter_map(|entry| {
+            let by_id = live.iter().position(|window| {
+                window.id == entry.id && window.pid == entry.pid && window.app_id == entry.app_id
+            });
+            let index = by_id.filter(|&index| !matched[index]).or_else(|| {
+                live.iter()
+                    .enumerate()
+                    .position(|(index, window)| !matched[index] && window.same_window(entry))
+            })?;
+            matched[index] = true;
+            Some(live[index].id)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{reconcile, Entry};
+
+    fn entry(id: i64, pid: i32, app_id: &str, title: &str) -> Entry {
+        Entry {
+            id,
+            pid: Some(pid),
+            app_id: Some(app_id.to_string()),
+            title: Some(title.to_string()),
+        }
+    }
+
+    #[test]
+    fn reconcile_ids() {
+        let saved = vec![
+            entry(3, 30, "foot", "vim"),
+            entry(1, 10, "firefox", "news"),
+            entry(2, 20, "foot", "htop"),
+        ];
+        let live = vec![
+            entry(1, 10, "firefox", "mail"),
+            entry(2, 20, "foot", "htop"),
+            entry(4, 40, "mpv", "video"),
+        ];
+
+        assert_eq!(reconcile(&saved, &live), vec![1, 2]);
+    }
+
+    #[test]
+    fn reconcile_changed_ids() {
+        let saved = vec![
+            entry(3, 30, "foot", "vim"),
+            entry(1, 10, "firefox", "news"),
+            entry(2, 30, "foot", "htop"),
+        ];
+        let live = vec![
+            entry(1, 30, "foot", "htop"),
+            entry(2, 10, "firefox", "news"),
+            entry(5, 30, "foot", "vim"),
+        ];
+
+        assert_eq!(reconcile(&saved, &live), vec![5, 2, 1]);
+    }
+
+    #[test]
+    fn reconcile_empty() {
+        let live = vec![entry(1, 10, "firefox", "news")];
+
+        assert_eq!(reconcile(&[], &live), Vec::<i64>::new());
+        assert_eq!(reconcile(&live, &[]), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn serialize() {
+        let entries = vec![entry(1, 10, "firefox", "news")];
+        let json = serde_json::to_string(&entries).unwrap();
+
+        assert_eq!(
+            json,
+            r#"[{"id":1,"pid":10,"app_id":"firefox","title":"news"}]"#
+        );
+        assert_eq!(serde_json::from_str::<Vec<Entry>>(&json).unwrap(), entries);
+    }
+}
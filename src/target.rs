@@ -0,0 +1,30 @@
+use std::str::FromStr;
+
+/// `Target` is kind of things for switching between
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// windows
+    Window,
+    /// workspaces
+    Workspace,
+    /// outputs
+    Output,
+}
+
+impl Target {
+    /// Names of all targets
+    pub const NAMES: &'static [&'static str] = &["window", "workspace", "output"];
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "window" => Ok(Self::Window),
+            "workspace" => Ok(Self::Workspace),
+            "output" => Ok(Self::Output),
+            _ => Err(format!("incorrect target {}", s)),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use {crate::criteria::Window, std::time::SystemTime};
+
+/// `WindowInfo` is properties of window remembered alongside its place in
+/// the stack
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct WindowInfo {
+    pub title: Option<String>,
+    /// `app_id` of wayland window
+    pub app_id: Option<String>,
+    /// `class` of X11 window
+    pub class: Option<String>,
+    pub pid: Option<i32>,
+    pub workspace: Option<String>,
+    pub output: Option<String>,
+    pub marks: Vec<String>,
+    pub floating: bool,
+    pub fullscreen: bool,
+    pub urgent: bool,
+    /// time of the last focusing of window
+    pub focused_at: Option<SystemTime>,
+}
+
+impl WindowInfo {
+    /// Get application of window: `app_id` for wayland or `class` for X11
+    pub fn app(&self) -> Option<&str> {
+        self.app_id.as_deref().or(self.class.as_deref())
+    }
+
+    /// Get properties of window for matching with
+    /// [`Criteria`](crate::criteria::Criteria)
+    pub fn window(&self) -> Window<'_> {
+        Window {
+            app_id: self.app_id.as_deref(),
+            class: self.class.as_deref(),
+            title: self.title.as_deref(),
+            workspace: self.workspace.as_deref(),
+            marks: &self.marks,
+            floating: self.floating,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::WindowInfo;
+
+    #[test]
+    fn app() {
+        let wayland = WindowInfo {
+            app_id: Some("foot".to_string()),
+            ..WindowInfo::default()
+        };
+        let x11 = WindowInfo {
+            class: Some("Gimp".to_string()),
+            ..WindowInfo::default()
+        };
+
+        assert_eq!(wayland.app(), Some("foot"));
+        assert_eq!(x11.app(), Some("Gimp"));
+        assert_eq!(WindowInfo::default().app(), None);
+    }
+}